@@ -55,8 +55,8 @@ fn main() -> Result<()> {
             let ev = ev_ctx.wait_event(600.).unwrap_or(Err(Error::Null));
 
             match ev {
-                Ok(Event::EndFile(r)) => {
-                    println!("Exiting! Reason: {:?}", r);
+                Ok(Event::EndFile { reason, .. }) => {
+                    println!("Exiting! Reason: {:?}", reason);
                     break;
                 }
 
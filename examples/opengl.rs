@@ -75,8 +75,9 @@ fn main() {
     render_context.set_update_callback(move || {
         event_proxy.send_event(MPVEvent::RenderUpdate).unwrap();
     });
+    let mut ev_ctx = mpv.create_event_context();
     let event_proxy = events_loop.create_proxy();
-    mpv.event_context_mut().set_wakeup_callback(move || {
+    ev_ctx.set_wakeup_callback(move || {
         event_proxy.send_event(MPVEvent::EventUpdate).unwrap();
     });
     mpv.playlist_load_files(&[(&path, FileState::AppendPlay, None)])
@@ -106,8 +107,8 @@ fn main() {
                     window.window().request_redraw();
                 }
                 MPVEvent::EventUpdate => loop {
-                    match mpv.event_context_mut().wait_event(0.0) {
-                        Some(Ok(libmpv::events::Event::EndFile(_))) => {
+                    match ev_ctx.wait_event(0.0) {
+                        Some(Ok(libmpv::events::Event::EndFile { .. })) => {
                             *control_flow = ControlFlow::Exit;
                             break;
                         }
@@ -0,0 +1,158 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+//! Derive macros for [libmpv](https://crates.io/crates/libmpv), enabled by its `derive` feature.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr};
+
+/// Implement `libmpv::properties::MpvProperties` for a struct with named fields.
+///
+/// Each field is bound to the property named by `#[mpv(name = "...")]`, or to its name with `_`
+/// replaced by `-`. Fields marked with `#[mpv(skip)]` are ignored.
+#[proc_macro_derive(MpvProperties, attributes(mpv))]
+pub fn derive_mpv_properties(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+struct Field {
+    ident: syn::Ident,
+    ty: syn::Type,
+    property: String,
+}
+
+fn parse_field(field: &syn::Field) -> syn::Result<Option<Field>> {
+    let ident = field.ident.clone().expect("named field");
+    let mut property = ident.to_string().trim_start_matches("r#").replace('_', "-");
+    let mut skip = false;
+
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("mpv"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                property = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `name = \"...\"` or `skip`"))
+            }
+        })?;
+    }
+
+    if skip {
+        Ok(None)
+    } else {
+        Ok(Some(Field {
+            ident,
+            ty: field.ty.clone(),
+            property,
+        }))
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input,
+                    "MpvProperties can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input,
+                "MpvProperties can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut bound = Vec::new();
+    for field in fields {
+        if let Some(field) = parse_field(field)? {
+            if let Some(other) = bound.iter().find(|f: &&Field| f.property == field.property) {
+                return Err(Error::new_spanned(
+                    &field.ident,
+                    format!(
+                        "property `{}` is already bound to `{}`",
+                        field.property, other.ident
+                    ),
+                ));
+            }
+            bound.push(field);
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let properties = bound.iter().map(|Field { ty, property, .. }| {
+        quote! {
+            (
+                #property,
+                <<#ty as ::libmpv::properties::PropertyField>::Data as ::libmpv::GetData>::get_format(),
+            )
+        }
+    });
+    let arms = bound.iter().map(|Field { ident, ty, property }| {
+        quote! {
+            #property => <#ty as ::libmpv::properties::PropertyField>::apply(&mut self.#ident, change),
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::libmpv::properties::MpvProperties for #name #ty_generics #where_clause {
+            fn properties() -> ::std::vec::Vec<(&'static str, ::libmpv::Format)> {
+                ::std::vec![#(#properties),*]
+            }
+
+            fn apply_change(
+                &mut self,
+                group: &::libmpv::events::ObserverGroup,
+                event: &::libmpv::events::Event,
+            ) -> ::libmpv::Result<bool> {
+                match *event {
+                    ::libmpv::events::Event::PropertyChange {
+                        name,
+                        ref change,
+                        reply_userdata,
+                    } if reply_userdata == group.id() => match name {
+                        #(#arms)*
+                        _ => ::std::result::Result::Ok(false),
+                    },
+                    _ => ::std::result::Result::Ok(false),
+                }
+            }
+        }
+    })
+}
@@ -42,11 +42,16 @@ pub const MPV_CLIENT_API_MINOR: ctype::c_ulong = 0;
 pub const MPV_CLIENT_API_VERSION: ctype::c_ulong =
     MPV_CLIENT_API_MAJOR << 16 | MPV_CLIENT_API_MINOR;
 
+// Allows `#[derive(MpvProperties)]`, which refers to `::libmpv`, within this crate
+extern crate self as libmpv;
+
 mod mpv;
 #[cfg(test)]
 mod tests;
 
 pub use crate::mpv::*;
+#[cfg(feature = "derive")]
+pub use libmpv_derive::MpvProperties;
 
 /// A format mpv can use.
 pub use libmpv_sys::mpv_format as MpvFormat;
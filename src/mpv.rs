@@ -27,10 +27,57 @@ macro_rules! mpv_cstr_to_str {
     };
 }
 
-mod errors;
+/// Implement `GetData` and `ObserveData` for a type that is parsed from an `OwnedNode`.
+macro_rules! impl_node_data {
+    ($ty: ty, $from_node: expr) => {
+        unsafe impl crate::GetData for $ty {
+            fn get_from_c_void<T, F: FnMut(*mut ctype::c_void) -> Result<T>>(
+                fun: F,
+            ) -> Result<$ty> {
+                $from_node(&OwnedNode::get_from_c_void(fun)?)
+            }
+
+            fn get_format() -> Format {
+                Format::Node
+            }
+        }
+
+        impl crate::events::ObserveData for $ty {
+            fn from_property_data(
+                data: &crate::events::PropertyData,
+            ) -> Result<crate::events::ObservedValue<$ty>> {
+                use crate::events::{ObserveData, ObservedValue};
+                Ok(
+                    match <OwnedNode as ObserveData>::from_property_data(data)? {
+                        ObservedValue::Value(node) => ObservedValue::Value($from_node(&node)?),
+                        ObservedValue::Unavailable => ObservedValue::Unavailable,
+                    },
+                )
+            }
+        }
+    };
+}
 
+mod audio_devices;
+mod builder;
+mod chapters;
+mod config;
+mod errors;
+mod introspection;
+mod loops;
+mod media_params;
+mod metadata;
+mod playlist;
+mod playlist_files;
+mod seek;
+mod tracks;
+
+/// Dispatching events to registered handlers
+pub mod dispatcher;
 /// Event handling
 pub mod events;
+/// Typed property keys, and constants for common properties
+pub mod properties;
 /// Custom protocols (`protocol://$url`) for playback
 #[cfg(feature = "protocols")]
 pub mod protocol;
@@ -38,8 +85,18 @@ pub mod protocol;
 #[cfg(feature = "render")]
 pub mod render;
 
+pub use self::audio_devices::*;
+pub use self::builder::*;
+pub use self::chapters::*;
 pub use self::errors::*;
-use self::events::EventContext;
+pub use self::introspection::*;
+pub use self::loops::*;
+pub use self::media_params::*;
+pub use self::metadata::*;
+pub use self::playlist::*;
+pub use self::playlist_files::*;
+pub use self::seek::*;
+pub use self::tracks::*;
 use super::*;
 
 use std::{
@@ -48,7 +105,10 @@ use std::{
     ops::Deref,
     os::raw as ctype,
     ptr::{self, NonNull},
-    sync::atomic::AtomicBool,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Condvar, Mutex, Weak,
+    },
 };
 
 fn mpv_err<T>(ret: T, err: ctype::c_int) -> Result<T> {
@@ -160,6 +220,7 @@ impl<'parent> Iterator for MpvNodeMapIter<'parent> {
 }
 
 #[derive(Debug)]
+#[repr(transparent)]
 pub struct MpvNode(libmpv_sys::mpv_node);
 
 impl Drop for MpvNode {
@@ -241,6 +302,241 @@ impl MpvNode {
             None
         }
     }
+
+    /// Recursively copy this node into an `OwnedNode`, that does not borrow from mpv.
+    pub fn to_owned_node(&self) -> Result<OwnedNode> {
+        // SAFETY: `self.0` is a valid node for as long as `self` lives
+        unsafe { OwnedNode::from_raw(&self.0) }
+    }
+}
+
+unsafe impl GetData for OwnedNode {
+    fn get_from_c_void<T, F: FnMut(*mut ctype::c_void) -> Result<T>>(fun: F) -> Result<OwnedNode> {
+        MpvNode::get_from_c_void(fun)?.to_owned_node()
+    }
+
+    fn get_format() -> Format {
+        Format::Node
+    }
+}
+
+/// An owned copy of an `MpvNode`, that can be freely cloned and sent across threads.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedNode {
+    String(String),
+    Flag(bool),
+    Int64(i64),
+    Double(f64),
+    Array(Vec<OwnedNode>),
+    /// Key-value pairs, in the order mpv returned them.
+    Map(Vec<(String, OwnedNode)>),
+    None,
+}
+
+impl OwnedNode {
+    // SAFETY: `node` has to be a valid `mpv_node`, as returned by mpv.
+    pub(crate) unsafe fn from_raw(node: &libmpv_sys::mpv_node) -> Result<OwnedNode> {
+        Ok(match node.format {
+            mpv_format::Flag => OwnedNode::Flag(node.u.flag == 1),
+            mpv_format::Int64 => OwnedNode::Int64(node.u.int64),
+            mpv_format::Double => OwnedNode::Double(node.u.double_),
+            mpv_format::String => OwnedNode::String(mpv_cstr_to_str!(node.u.string)?.to_owned()),
+            mpv_format::Array => {
+                let list = *node.u.list;
+                let mut values = Vec::with_capacity(list.num.max(0) as _);
+                for i in 0..list.num.max(0) as isize {
+                    values.push(OwnedNode::from_raw(&*list.values.offset(i))?);
+                }
+                OwnedNode::Array(values)
+            }
+            mpv_format::Map => {
+                let list = *node.u.list;
+                let mut pairs = Vec::with_capacity(list.num.max(0) as _);
+                for i in 0..list.num.max(0) as isize {
+                    pairs.push((
+                        mpv_cstr_to_str!(*list.keys.offset(i))?.to_owned(),
+                        OwnedNode::from_raw(&*list.values.offset(i))?,
+                    ));
+                }
+                OwnedNode::Map(pairs)
+            }
+            mpv_format::None => OwnedNode::None,
+            _ => return Err(Error::Raw(mpv_error::PropertyError)),
+        })
+    }
+
+    pub fn to_bool(&self) -> Option<bool> {
+        if let OwnedNode::Flag(value) = *self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if let OwnedNode::Int64(value) = *self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// Also converts `Int64` nodes, as mpv may return integral numbers for doubles.
+    pub fn to_f64(&self) -> Option<f64> {
+        match *self {
+            OwnedNode::Double(value) => Some(value),
+            OwnedNode::Int64(value) => Some(value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> Option<&str> {
+        if let OwnedNode::String(ref value) = *self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_array(&self) -> Option<&[OwnedNode]> {
+        if let OwnedNode::Array(ref values) = *self {
+            Some(values)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_map(&self) -> Option<&[(String, OwnedNode)]> {
+        if let OwnedNode::Map(ref pairs) = *self {
+            Some(pairs)
+        } else {
+            None
+        }
+    }
+
+    /// The value of `key`, if this is a map that contains it.
+    pub fn get(&self, key: &str) -> Option<&OwnedNode> {
+        self.to_map()?
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value)
+    }
+}
+
+/// An `mpv_node` converted from an `OwnedNode`, that owns the memory the node points to.
+struct RawNode {
+    node: libmpv_sys::mpv_node,
+    strings: Vec<CString>,
+    /// Reserved up front, so that pushing never moves the lists the nodes point to
+    lists: Vec<libmpv_sys::mpv_node_list>,
+    values: Vec<Vec<libmpv_sys::mpv_node>>,
+    keys: Vec<Vec<*mut ctype::c_char>>,
+}
+
+impl RawNode {
+    fn new(node: &OwnedNode) -> Result<RawNode> {
+        let mut raw = RawNode {
+            node: libmpv_sys::mpv_node {
+                u: libmpv_sys::mpv_node__bindgen_ty_1 { int64: 0 },
+                format: mpv_format::None,
+            },
+            strings: Vec::new(),
+            lists: Vec::with_capacity(RawNode::count_lists(node)),
+            values: Vec::new(),
+            keys: Vec::new(),
+        };
+        raw.node = raw.convert(node)?;
+        Ok(raw)
+    }
+
+    fn count_lists(node: &OwnedNode) -> usize {
+        match *node {
+            OwnedNode::Array(ref values) => {
+                1 + values.iter().map(RawNode::count_lists).sum::<usize>()
+            }
+            OwnedNode::Map(ref pairs) => {
+                1 + pairs
+                    .iter()
+                    .map(|(_, value)| RawNode::count_lists(value))
+                    .sum::<usize>()
+            }
+            _ => 0,
+        }
+    }
+
+    fn string(&mut self, value: &str) -> Result<*mut ctype::c_char> {
+        let value = CString::new(value)?;
+        let ptr = value.as_ptr() as *mut _;
+        self.strings.push(value);
+        Ok(ptr)
+    }
+
+    fn list(
+        &mut self,
+        mut values: Vec<libmpv_sys::mpv_node>,
+        mut keys: Option<Vec<*mut ctype::c_char>>,
+    ) -> Result<*mut libmpv_sys::mpv_node_list> {
+        self.lists.push(libmpv_sys::mpv_node_list {
+            num: values
+                .len()
+                .try_into()
+                .map_err(|_| Error::Raw(mpv_error::InvalidParameter))?,
+            values: values.as_mut_ptr(),
+            keys: keys
+                .as_mut()
+                .map(|keys| keys.as_mut_ptr())
+                .unwrap_or(ptr::null_mut()),
+        });
+        // Moving the vectors does not move the memory they point to
+        self.values.push(values);
+        self.keys.extend(keys);
+        Ok(self.lists.last_mut().unwrap() as *mut _)
+    }
+
+    fn convert(&mut self, node: &OwnedNode) -> Result<libmpv_sys::mpv_node> {
+        use libmpv_sys::mpv_node__bindgen_ty_1 as Value;
+
+        let (u, format) = match *node {
+            OwnedNode::String(ref value) => (
+                Value {
+                    string: self.string(value)?,
+                },
+                mpv_format::String,
+            ),
+            OwnedNode::Flag(value) => (Value { flag: value as _ }, mpv_format::Flag),
+            OwnedNode::Int64(value) => (Value { int64: value }, mpv_format::Int64),
+            OwnedNode::Double(value) => (Value { double_: value }, mpv_format::Double),
+            OwnedNode::Array(ref values) => {
+                let values = values
+                    .iter()
+                    .map(|value| self.convert(value))
+                    .collect::<Result<Vec<_>>>()?;
+                (
+                    Value {
+                        list: self.list(values, None)?,
+                    },
+                    mpv_format::Array,
+                )
+            }
+            OwnedNode::Map(ref pairs) => {
+                let mut keys = Vec::with_capacity(pairs.len());
+                let mut values = Vec::with_capacity(pairs.len());
+                for (key, value) in pairs {
+                    keys.push(self.string(key)?);
+                    values.push(self.convert(value)?);
+                }
+                (
+                    Value {
+                        list: self.list(values, Some(keys))?,
+                    },
+                    mpv_format::Map,
+                )
+            }
+            OwnedNode::None => (Value { int64: 0 }, mpv_format::None),
+        };
+        Ok(libmpv_sys::mpv_node { u, format })
+    }
 }
 
 unsafe impl GetData for MpvNode {
@@ -391,9 +687,11 @@ impl FileState {
     }
 }
 
-/// Context passed to the `initializer` of `Mpv::with_initialzer`.
+/// Context passed to the `initializer` of `Mpv::with_initializer` and
+/// `MpvBuilder::build_with_initializer`.
 pub struct MpvInitializer {
     ctx: *mut libmpv_sys::mpv_handle,
+    state: Arc<CoreState>,
 }
 
 impl MpvInitializer {
@@ -417,29 +715,175 @@ impl MpvInitializer {
             })
         })
     }
+
+    /// Apply mpv command line arguments, and return the files that should be played, e.g. with
+    /// `Mpv::playlist_load_files`. `args` should not contain the program name.
+    ///
+    /// Options start with `--`, and are set with `mpv_set_option_string`, so they use the same
+    /// syntax as on the command line: `--opt=value`, `--opt` and `--no-opt` for flags, list
+    /// suffixes such as `--opt-append=value`, and `--profile=name`. All arguments after `--`, and
+    /// arguments that don't start with `--`, e.g. `-` for stdin, are files. Per-file option groups
+    /// (`--{` and `--}`) are not supported.
+    ///
+    /// Returns `Error::Argument` with the index of the first argument that could not be applied.
+    pub fn apply_args<I, S>(&self, args: I) -> Result<Vec<String>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut files = Vec::new();
+        let mut options_done = false;
+
+        for (index, arg) in args.into_iter().enumerate() {
+            let arg = arg.as_ref();
+            if options_done || !arg.starts_with("--") {
+                files.push(arg.to_owned());
+                continue;
+            }
+
+            let res = match &arg[2..] {
+                "" => {
+                    options_done = true;
+                    Ok(())
+                }
+                "{" | "}" => Err(Error::Raw(mpv_error::Unsupported)),
+                opt => {
+                    // Flags without a value behave as on the command line, e.g. `--no-opt`
+                    let (name, value) = match opt.find('=') {
+                        Some(i) => (&opt[..i], &opt[i + 1..]),
+                        None => (opt, ""),
+                    };
+                    self.set_option_string(name, value)
+                }
+            };
+            res.map_err(|error| Error::Argument {
+                index,
+                error: ::std::rc::Rc::new(error),
+            })?;
+        }
+
+        Ok(files)
+    }
+
+    fn set_option_string(&self, name: &str, value: &str) -> Result<()> {
+        let name = CString::new(name)?;
+        let value = CString::new(value)?;
+        mpv_err((), unsafe {
+            libmpv_sys::mpv_set_option_string(self.ctx, name.as_ptr(), value.as_ptr())
+        })
+    }
 }
 
-/// The central mpv context.
-pub struct Mpv {
-    /// The handle to the mpv core
-    pub ctx: NonNull<libmpv_sys::mpv_handle>,
-    event_context: EventContext,
-    #[cfg(feature = "protocols")]
-    protocols_guard: AtomicBool,
-    events_guard: AtomicBool,
+/// The state shared by all clients of a core.
+#[derive(Default)]
+struct CoreState {
+    /// The exit code passed to `Mpv::quit`
+    exit_code: Mutex<Option<i32>>,
+    /// Whether the handle created by `mpv_create` was destroyed, signalled by `destroyed_cond`
+    destroyed: Mutex<bool>,
+    destroyed_cond: Condvar,
+    /// The profiles defined by `Mpv::load_config_str`
+    profiles: Mutex<config::Profiles>,
+    /// The titles of the entries loaded by `Mpv::playlist_load_entries`, by entry id
+    titles: Mutex<std::collections::HashMap<i64, String>>,
 }
 
-unsafe impl Send for Mpv {}
-unsafe impl Sync for Mpv {}
+/// The handle created by `mpv_create`, that terminates the core when the last strong client
+/// drops it.
+struct Core {
+    ctx: NonNull<libmpv_sys::mpv_handle>,
+    detached: bool,
+    state: Arc<CoreState>,
+}
+
+unsafe impl Send for Core {}
+unsafe impl Sync for Core {}
 
-impl Drop for Mpv {
+impl Drop for Core {
     fn drop(&mut self) {
         unsafe {
-            libmpv_sys::mpv_terminate_destroy(self.ctx.as_ptr());
+            if self.detached {
+                libmpv_sys::mpv_destroy(self.ctx.as_ptr());
+            } else {
+                libmpv_sys::mpv_terminate_destroy(self.ctx.as_ptr());
+            }
         }
+        *self.state.destroyed.lock().unwrap() = true;
+        self.state.destroyed_cond.notify_all();
     }
 }
 
+/// The reference of a client to the core, which only keeps it alive for strong clients.
+#[derive(Clone)]
+enum CoreRef {
+    Strong(Arc<Core>),
+    Weak(Weak<Core>),
+}
+
+/// A client handle, shared by all clones of an `Mpv` and its `EventContext`. The handle is
+/// destroyed when the last of them is dropped.
+pub(crate) struct Client {
+    pub(crate) ctx: NonNull<libmpv_sys::mpv_handle>,
+    /// Only taken when the client is dropped
+    core: Option<CoreRef>,
+    detached: AtomicBool,
+    state: Arc<CoreState>,
+    #[cfg(feature = "protocols")]
+    pub(crate) protocols_guard: AtomicBool,
+    pub(crate) events_guard: AtomicBool,
+    /// The events enabled with `mpv_request_event`, which outlive an `EventContext`
+    pub(crate) enabled_events: AtomicU64,
+    /// The next id allocated by `EventContext::observe`, unique for as long as the client lives
+    pub(crate) next_observer_id: AtomicU64,
+}
+
+unsafe impl Send for Client {}
+unsafe impl Sync for Client {}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let core = match self.core.take() {
+            Some(CoreRef::Strong(core)) => core,
+            _ => {
+                unsafe { libmpv_sys::mpv_destroy(self.ctx.as_ptr()) };
+                return;
+            }
+        };
+        // The handle of the core is destroyed by the last strong client
+        if core.ctx != self.ctx {
+            unsafe { libmpv_sys::mpv_destroy(self.ctx.as_ptr()) };
+        }
+
+        {
+            // Only the last strong client decides whether the core is terminated
+            if self.detached.load(Ordering::Acquire) {
+                if let Ok(mut core) = Arc::try_unwrap(core) {
+                    core.detached = true;
+                }
+            }
+        }
+    }
+}
+
+/// The central mpv context.
+///
+/// `Mpv` is a cheaply cloneable handle to a client, that can be shared between threads to
+/// control the player. Events are received by the single `EventContext` of the client, see
+/// `Mpv::create_event_context`. The client is destroyed when all clones and its `EventContext` are
+/// dropped.
+///
+/// Additional clients of the same core are created with `Mpv::create_client` and
+/// `Mpv::create_weak_client`. The core is terminated when the last strong client is destroyed.
+#[derive(Clone)]
+pub struct Mpv {
+    /// The handle to the mpv core
+    pub ctx: NonNull<libmpv_sys::mpv_handle>,
+    pub(crate) client: Arc<Client>,
+}
+
+unsafe impl Send for Mpv {}
+unsafe impl Sync for Mpv {}
+
 impl Mpv {
     /// Create a new `Mpv`.
     /// The default settings can be probed by running: `$ mpv --show-profile=libmpv`.
@@ -452,41 +896,140 @@ impl Mpv {
     pub fn with_initializer<F: FnOnce(MpvInitializer) -> Result<()>>(
         initializer: F,
     ) -> Result<Mpv> {
-        let api_version = unsafe { libmpv_sys::mpv_client_api_version() };
-        if crate::MPV_CLIENT_API_MAJOR != api_version >> 16 {
-            return Err(Error::VersionMismatch {
-                linked: crate::MPV_CLIENT_API_VERSION,
-                loaded: api_version,
-            });
-        }
+        MpvBuilder::new().build_with_initializer(initializer)
+    }
 
-        let ctx = unsafe { libmpv_sys::mpv_create() };
-        if ctx.is_null() {
-            return Err(Error::Null);
-        }
+    /// Create an `MpvBuilder` to set options before `Mpv` is initialized.
+    pub fn builder() -> MpvBuilder {
+        MpvBuilder::new()
+    }
 
-        initializer(MpvInitializer { ctx })?;
-        unsafe {
-            libmpv_sys::mpv_set_option_string(
+    fn from_handle(
+        ctx: NonNull<libmpv_sys::mpv_handle>,
+        core: Option<CoreRef>,
+        state: Arc<CoreState>,
+    ) -> Mpv {
+        Mpv {
+            ctx,
+            client: Arc::new(Client {
                 ctx,
-                b"osd-level\0".as_ptr() as _,
-                b"0\0".as_ptr() as _,
-            );
+                core,
+                detached: AtomicBool::new(false),
+                state,
+                events_guard: AtomicBool::new(false),
+                enabled_events: AtomicU64::new(events::EventMask::mpv_default().0),
+                next_observer_id: AtomicU64::new(events::OBSERVER_IDS_START),
+                #[cfg(feature = "protocols")]
+                protocols_guard: AtomicBool::new(false),
+            }),
         }
-        mpv_err((), unsafe { libmpv_sys::mpv_initialize(ctx) }).map_err(|err| {
-            unsafe { libmpv_sys::mpv_terminate_destroy(ctx) };
-            err
-        })?;
+    }
 
-        let ctx = unsafe { NonNull::new_unchecked(ctx) };
+    /// Create a new client of the same core, with its own event queue and observed properties.
+    /// The client is already initialized, and keeps the core alive until it is destroyed.
+    ///
+    /// `name` is used for e.g. `script-message-to`, and is made unique by mpv if necessary,
+    /// see `Mpv::client_name`.
+    ///
+    /// Fails with `Error::Raw(mpv_error::Uninitialized)` if this is a weak client whose core was
+    /// already terminated.
+    pub fn create_client(&self, name: &str) -> Result<Mpv> {
+        let core = match self.client.core {
+            Some(CoreRef::Strong(ref core)) => core.clone(),
+            Some(CoreRef::Weak(ref core)) => {
+                core.upgrade().ok_or(Error::Raw(mpv_error::Uninitialized))?
+            }
+            None => unreachable!("every client references the core"),
+        };
+        let name = CString::new(name)?;
+        let ctx = unsafe { libmpv_sys::mpv_create_client(self.ctx.as_ptr(), name.as_ptr()) };
+        let ctx = NonNull::new(ctx).ok_or(Error::Null)?;
+        Ok(Mpv::from_handle(
+            ctx,
+            Some(CoreRef::Strong(core)),
+            self.client.state.clone(),
+        ))
+    }
 
-        Ok(Mpv {
+    /// Create a new client of the same core, like `Mpv::create_client`, that does not keep the
+    /// core alive. When the last strong client is destroyed, the core is terminated and this
+    /// client receives `Event::Shutdown`. Destroying the last strong client blocks until weak
+    /// clients are destroyed, so they should be dropped after receiving `Event::Shutdown`.
+    pub fn create_weak_client(&self, name: &str) -> Result<Mpv> {
+        let name = CString::new(name)?;
+        let ctx = unsafe { libmpv_sys::mpv_create_weak_client(self.ctx.as_ptr(), name.as_ptr()) };
+        let ctx = NonNull::new(ctx).ok_or(Error::Null)?;
+        let core = match self.client.core {
+            Some(CoreRef::Strong(ref core)) => Arc::downgrade(core),
+            Some(CoreRef::Weak(ref core)) => core.clone(),
+            None => unreachable!("every client references the core"),
+        };
+        Ok(Mpv::from_handle(
             ctx,
-            events_guard: AtomicBool::new(false),
-            event_context: EventContext::new(ctx),
-            #[cfg(feature = "protocols")]
-            protocols_guard: AtomicBool::new(false),
-        })
+            Some(CoreRef::Weak(core)),
+            self.client.state.clone(),
+        ))
+    }
+
+    /// Quit the player, drop this handle, and block until the core is terminated.
+    ///
+    /// All clients receive `Event::Shutdown`, and the core is terminated once every strong
+    /// client is destroyed, i.e. all their clones and `EventContext`s are dropped, e.g. after
+    /// `EventContext::wait_for_shutdown`. If the last strong client was detached, this returns
+    /// once its handle is destroyed.
+    ///
+    /// Other strong clients have to be dropped by other threads, or this never returns. A clone
+    /// of this handle or its `EventContext` can't be dropped while waiting, so if one is still
+    /// alive, `Error::Raw(mpv_error::InvalidParameter)` is returned without quitting.
+    pub fn terminate(self) -> Result<()> {
+        if Arc::strong_count(&self.client) > 1 {
+            return Err(Error::Raw(mpv_error::InvalidParameter));
+        }
+
+        let state = self.client.state.clone();
+        // Fails if the core is already shutting down, which is what we want
+        let _ = self.command("quit", &[]);
+        drop(self);
+
+        let mut destroyed = state.destroyed.lock().unwrap();
+        while !*destroyed {
+            destroyed = state.destroyed_cond.wait(destroyed).unwrap();
+        }
+        Ok(())
+    }
+
+    /// Drop this handle without quitting the player. If the client is the last strong client of
+    /// the core, its handle is destroyed with `mpv_destroy`, which does not block, and the core
+    /// keeps running until other clients of it, e.g. scripts, are done.
+    ///
+    /// Note that the client is only detached once all its clones and its `EventContext` are
+    /// dropped.
+    pub fn detach(self) {
+        self.client.detached.store(true, Ordering::Release);
+    }
+
+    /// Quit the player with the process exit code `code`, which can be read back with
+    /// `Mpv::exit_code` by all clients of the core.
+    pub fn quit(&self, code: i32) -> Result<()> {
+        *self.client.state.exit_code.lock().unwrap() = Some(code);
+        self.command("quit", &[&code.to_string()])
+    }
+
+    /// The exit code passed to `Mpv::quit` of any client of the core, or `None` if it was not
+    /// called. Quitting by other means, e.g. key bindings or scripts, is not reflected.
+    pub fn exit_code(&self) -> Option<i32> {
+        *self.client.state.exit_code.lock().unwrap()
+    }
+
+    /// The unique name of this client. The handle created by `Mpv::new` is named `"main"`.
+    pub fn client_name(&self) -> Result<&str> {
+        unsafe { mpv_cstr_to_str!(libmpv_sys::mpv_client_name(self.ctx.as_ptr())) }
+    }
+
+    /// The unique ID of this client, which is never reused by the core. Some commands accept
+    /// `"@<id>"` in place of the client name.
+    pub fn client_id(&self) -> i64 {
+        unsafe { libmpv_sys::mpv_client_id(self.ctx.as_ptr()) }
     }
 
     /// Load a configuration file. The path has to be absolute, and a file.
@@ -499,14 +1042,6 @@ impl Mpv {
         ret
     }
 
-    pub fn event_context(&self) -> &EventContext {
-        &self.event_context
-    }
-
-    pub fn event_context_mut(&mut self) -> &mut EventContext {
-        &mut self.event_context
-    }
-
     /// Send a command to the `Mpv` instance. This uses `mpv_command_string` internally,
     /// so that the syntax is the same as described in the [manual for the input.conf](https://mpv.io/manual/master/#list-of-input-commands).
     ///
@@ -525,6 +1060,81 @@ impl Mpv {
         })
     }
 
+    /// Run the command `name` with `args` and return its result, see `mpv_command_ret`.
+    /// Unlike with `command`, the arguments are passed as they are, so they must not be escaped.
+    pub fn command_ret(&self, name: &str, args: &[&str]) -> Result<OwnedNode> {
+        let args = Some(name)
+            .iter()
+            .chain(args)
+            .map(|arg| CString::new(*arg))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let mut raw = args.iter().map(|arg| arg.as_ptr()).collect::<Vec<_>>();
+        raw.push(ptr::null());
+
+        OwnedNode::get_from_c_void(|result| {
+            mpv_err((), unsafe {
+                libmpv_sys::mpv_command_ret(self.ctx.as_ptr(), raw.as_mut_ptr(), result as *mut _)
+            })
+        })
+    }
+
+    /// Run the command `name` with `args` asynchronously. Its result is received as
+    /// `Event::CommandReply` with `reply_userdata`.
+    pub fn command_async(&self, reply_userdata: u64, name: &str, args: &[&str]) -> Result<()> {
+        let args = Some(name)
+            .iter()
+            .chain(args)
+            .map(|arg| CString::new(*arg))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let mut raw = args.iter().map(|arg| arg.as_ptr()).collect::<Vec<_>>();
+        raw.push(ptr::null());
+
+        mpv_err((), unsafe {
+            libmpv_sys::mpv_command_async(self.ctx.as_ptr(), reply_userdata, raw.as_mut_ptr())
+        })
+    }
+
+    /// Run the command `args`, which is either an array of the command name and its arguments,
+    /// or a map of named arguments with the command name as `name`, and return its result.
+    pub fn command_node(&self, args: &OwnedNode) -> Result<OwnedNode> {
+        let mut args = RawNode::new(args)?;
+        OwnedNode::get_from_c_void(|result| {
+            mpv_err((), unsafe {
+                libmpv_sys::mpv_command_node(self.ctx.as_ptr(), &mut args.node, result as *mut _)
+            })
+        })
+    }
+
+    /// Run `loadfile` with named arguments, and return the playlist entry id of the new entry.
+    ///
+    /// Positional arguments can't be used for `options`, because mpv 0.38 inserted an `index`
+    /// argument before it.
+    pub(crate) fn loadfile(
+        &self,
+        path: &str,
+        flags: &str,
+        index: Option<usize>,
+        options: Option<OwnedNode>,
+    ) -> Result<i64> {
+        let mut args = vec![
+            ("name".to_owned(), OwnedNode::String("loadfile".to_owned())),
+            ("url".to_owned(), OwnedNode::String(path.to_owned())),
+            ("flags".to_owned(), OwnedNode::String(flags.to_owned())),
+        ];
+        if let Some(index) = index {
+            let index = index
+                .try_into()
+                .map_err(|_| Error::Raw(mpv_error::InvalidParameter))?;
+            args.push(("index".to_owned(), OwnedNode::Int64(index)));
+        }
+        args.extend(options.map(|options| ("options".to_owned(), options)));
+
+        self.command_node(&OwnedNode::Map(args))?
+            .get("playlist_entry_id")
+            .and_then(OwnedNode::to_i64)
+            .ok_or(Error::Raw(mpv_error::PropertyFormat))
+    }
+
     /// Set the value of a property.
     pub fn set_property<T: SetData>(&self, name: &str, data: T) -> Result<()> {
         let name = CString::new(name)?;
@@ -614,7 +1224,7 @@ impl Mpv {
 
     /// Seek to the given percentage of the playtime.
     pub fn seek_percent_absolute(&self, percent: usize) -> Result<()> {
-        self.command("seek", &[&format!("{}", percent), "relative-percent"])
+        self.command("seek", &[&format!("{}", percent), "absolute-percent"])
     }
 
     /// Revert the previous `seek_` call, can also revert itself.
@@ -713,26 +1323,46 @@ impl Mpv {
     ///     * a `FileState` - how the file will be opened
     ///     * an optional string slice - any additional options that will be set for this file
     ///
+    /// Returns the playlist entry ids of the new entries, in the order of `files`, see
+    /// `PlaylistTracker`.
+    ///
     /// # Peculiarities
     /// `loadfile` is kind of asynchronous, any additional option is set during loading,
     /// [specifics](https://github.com/mpv-player/mpv/issues/4089).
-    pub fn playlist_load_files(&self, files: &[(&str, FileState, Option<&str>)]) -> Result<()> {
+    pub fn playlist_load_files(
+        &self,
+        files: &[(&str, FileState, Option<&str>)],
+    ) -> Result<Vec<i64>> {
+        let mut ids = Vec::with_capacity(files.len());
         for (i, elem) in files.iter().enumerate() {
-            let args = elem.2.unwrap_or("");
-
-            let ret = self.command(
-                "loadfile",
-                &[&format!("\"{}\"", elem.0), elem.1.val(), args],
-            );
-
-            if let Err(err) = ret {
-                return Err(Error::Loadfiles {
-                    index: i,
-                    error: ::std::rc::Rc::new(err),
-                });
+            let options = elem.2.map(|options| OwnedNode::String(options.to_owned()));
+            match self.loadfile(elem.0, elem.1.val(), None, options) {
+                Ok(id) => ids.push(id),
+                Err(err) => {
+                    return Err(Error::Loadfiles {
+                        index: i,
+                        error: ::std::rc::Rc::new(err),
+                    })
+                }
             }
         }
-        Ok(())
+        Ok(ids)
+    }
+
+    /// Load the file at `path` like `Mpv::playlist_load_files`, with the per-file `options` as
+    /// pairs of option names and values, which don't have to be escaped. Returns the id of the
+    /// new entry.
+    pub fn playlist_load_file_with_options(
+        &self,
+        path: &str,
+        state: FileState,
+        options: &[(&str, &str)],
+    ) -> Result<i64> {
+        let options = options
+            .iter()
+            .map(|&(name, value)| (name.to_owned(), OwnedNode::String(value.to_owned())))
+            .collect();
+        self.loadfile(path, state.val(), None, Some(OwnedNode::Map(options)))
     }
 
     /// Load the given playlist file, that either replaces the current playlist, or appends to it.
@@ -775,45 +1405,27 @@ impl Mpv {
     /// Add and select the subtitle immediately.
     /// Specifying a language requires specifying a title.
     ///
-    /// # Panics
-    /// If a language but not title was specified.
+    /// Fails with `Error::Raw(mpv_error::InvalidParameter)` if a language but no title is given.
     pub fn subtitle_add_select(
         &self,
         path: &str,
         title: Option<&str>,
         lang: Option<&str>,
     ) -> Result<()> {
-        match (title, lang) {
-            (None, None) => self.command("sub-add", &[&format!("\"{}\"", path), "select"]),
-            (Some(t), None) => self.command("sub-add", &[&format!("\"{}\"", path), "select", t]),
-            (None, Some(_)) => panic!("Given subtitle language, but missing title"),
-            (Some(t), Some(l)) => {
-                self.command("sub-add", &[&format!("\"{}\"", path), "select", t, l])
-            }
-        }
+        self.track_add(TrackKind::Sub, path, "select", title, lang)
     }
 
     /// See `AddSelect`. "Don't select the subtitle.
     /// (Or in some special situations, let the default stream selection mechanism decide.)".
     ///
-    /// Returns an `Error::InvalidArgument` if a language, but not a title, was provided.
-    ///
-    /// # Panics
-    /// If a language but not title was specified.
+    /// Fails with `Error::Raw(mpv_error::InvalidParameter)` if a language but no title is given.
     pub fn subtitle_add_auto(
         &self,
         path: &str,
         title: Option<&str>,
         lang: Option<&str>,
     ) -> Result<()> {
-        match (title, lang) {
-            (None, None) => self.command("sub-add", &[&format!("\"{}\"", path), "auto"]),
-            (Some(t), None) => self.command("sub-add", &[&format!("\"{}\"", path), "auto", t]),
-            (Some(t), Some(l)) => {
-                self.command("sub-add", &[&format!("\"{}\"", path), "auto", t, l])
-            }
-            (None, Some(_)) => panic!("Given subtitle language, but missing title"),
-        }
+        self.track_add(TrackKind::Sub, path, "auto", title, lang)
     }
 
     /// See `AddSelect`. "Select the subtitle. If a subtitle with the same file name was
@@ -821,7 +1433,7 @@ impl Mpv {
     /// (In this case, title/language are ignored, and if the [sub] was changed since it was loaded,
     /// these changes won't be reflected.)".
     pub fn subtitle_add_cached(&self, path: &str) -> Result<()> {
-        self.command("sub-add", &[&format!("\"{}\"", path), "cached"])
+        self.track_add(TrackKind::Sub, path, "cached", None, None)
     }
 
     /// "Remove the given subtitle track. If the id argument is missing, remove the current
@@ -0,0 +1,78 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use crate::properties::{AUDIO_DEVICE, AUDIO_DEVICE_LIST};
+use crate::*;
+
+/// An audio output device, read from `audio-device-list`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AudioDevice {
+    /// The name used to select the device, e.g. `"pulse/alsa_output.pci-0000_00_1f.3"`.
+    pub name: String,
+    /// A human readable description of the device.
+    pub description: String,
+}
+
+impl AudioDevice {
+    /// Parse an entry of `audio-device-list`.
+    pub fn from_node(node: &OwnedNode) -> Result<AudioDevice> {
+        let str_of = |key| {
+            node.get(key)
+                .and_then(OwnedNode::to_str)
+                .map(str::to_owned)
+                .ok_or(Error::Raw(mpv_error::PropertyFormat))
+        };
+        Ok(AudioDevice {
+            name: str_of("name")?,
+            description: str_of("description")?,
+        })
+    }
+
+    fn list_from_node(node: &OwnedNode) -> Result<Vec<AudioDevice>> {
+        node.to_array()
+            .ok_or(Error::Raw(mpv_error::PropertyFormat))?
+            .iter()
+            .map(AudioDevice::from_node)
+            .collect()
+    }
+}
+
+impl_node_data!(Vec<AudioDevice>, AudioDevice::list_from_node);
+
+impl Mpv {
+    /// The audio output devices of all audio outputs. The first entry is always the `"auto"`
+    /// device, which lets the audio output choose.
+    ///
+    /// Observe `properties::AUDIO_DEVICE_LIST` to be notified when devices are added or
+    /// removed; mpv only watches for hotplug events while the list is observed.
+    pub fn audio_devices(&self) -> Result<Vec<AudioDevice>> {
+        self.get(&AUDIO_DEVICE_LIST)
+    }
+
+    /// The name of the selected audio device.
+    pub fn audio_device(&self) -> Result<String> {
+        self.get(&AUDIO_DEVICE)
+    }
+
+    /// Switch to the audio device with `name`, see `AudioDevice::name`. This reloads the audio
+    /// output if needed.
+    pub fn set_audio_device(&self, name: &str) -> Result<()> {
+        self.set_property(AUDIO_DEVICE.name(), name)
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use super::{mpv_err, Core, CoreRef, CoreState, MpvInitializer};
+use crate::*;
+
+use std::ffi::CString;
+use std::ptr::NonNull;
+use std::sync::Arc;
+
+/// When the player enters idle mode instead of quitting, see the `idle` option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Idle {
+    /// Quit when there is nothing to play.
+    No,
+    /// Wait in idle mode when there is nothing to play. This is the default of libmpv.
+    Yes,
+    /// Only wait in idle mode if no file was loaded on start.
+    Once,
+}
+
+impl Idle {
+    fn val(self) -> &'static str {
+        match self {
+            Idle::No => "no",
+            Idle::Yes => "yes",
+            Idle::Once => "once",
+        }
+    }
+}
+
+fn flag(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+fn log_level_name(level: LogLevel) -> Result<&'static str> {
+    Ok(match level {
+        mpv_log_level::None => "no",
+        mpv_log_level::Fatal => "fatal",
+        mpv_log_level::Error => "error",
+        mpv_log_level::Warn => "warn",
+        mpv_log_level::Info => "info",
+        mpv_log_level::V => "v",
+        mpv_log_level::Debug => "debug",
+        mpv_log_level::Trace => "trace",
+        _ => return Err(Error::Raw(mpv_error::InvalidParameter)),
+    })
+}
+
+/// Sets options before an `Mpv` is initialized, which is required for options that can't be
+/// changed at runtime. Options are applied in the order they were set, and the defaults can be
+/// probed by running: `$ mpv --show-profile=libmpv`.
+#[derive(Clone, Debug, Default)]
+pub struct MpvBuilder {
+    options: Vec<(String, String)>,
+    log_level: Option<LogLevel>,
+    config_file: Option<String>,
+}
+
+impl MpvBuilder {
+    /// Create a builder with the default options of libmpv.
+    pub fn new() -> MpvBuilder {
+        MpvBuilder::default()
+    }
+
+    /// Set the option `name` to `value`, as if by `--name=value` on the command line.
+    pub fn option(mut self, name: &str, value: &str) -> MpvBuilder {
+        self.options.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// The directory config files, scripts etc. are loaded from, see the `config-dir` option.
+    pub fn config_dir(self, path: &str) -> MpvBuilder {
+        self.option("config-dir", path)
+    }
+
+    /// Whether to load `mpv.conf` from the config dir. Disabled by default in libmpv.
+    pub fn config(self, load: bool) -> MpvBuilder {
+        self.option("config", flag(load))
+    }
+
+    /// Whether to use the terminal for input and output. Disabled by default in libmpv.
+    pub fn terminal(self, enable: bool) -> MpvBuilder {
+        self.option("terminal", flag(enable))
+    }
+
+    /// The log levels of the terminal output per module, e.g. `"all=warn,ao=v"`.
+    pub fn msg_level(self, levels: &str) -> MpvBuilder {
+        self.option("msg-level", levels)
+    }
+
+    /// The video output drivers, e.g. `"null"` or `"gpu,x11"`.
+    pub fn vo(self, drivers: &str) -> MpvBuilder {
+        self.option("vo", drivers)
+    }
+
+    /// The audio output drivers, e.g. `"null"` or `"pulse,alsa"`.
+    pub fn ao(self, drivers: &str) -> MpvBuilder {
+        self.option("ao", drivers)
+    }
+
+    /// When to enter idle mode instead of quitting.
+    pub fn idle(self, idle: Idle) -> MpvBuilder {
+        self.option("idle", idle.val())
+    }
+
+    /// Whether to use the default key bindings. Disabled by default in libmpv.
+    pub fn input_default_bindings(self, enable: bool) -> MpvBuilder {
+        self.option("input-default-bindings", flag(enable))
+    }
+
+    /// Whether to load scripts from the config dir, and builtin scripts such as the OSC.
+    pub fn load_scripts(self, load: bool) -> MpvBuilder {
+        self.option("load-scripts", flag(load))
+    }
+
+    /// Override the `osd-level`, e.g. `0` to disable the OSD completely.
+    pub fn osd_level(self, level: u8) -> MpvBuilder {
+        self.option("osd-level", &level.to_string())
+    }
+
+    /// Receive log messages of at least `level` as `Event::LogMessage` from the start, instead of
+    /// only after calling `mpv_request_log_messages` on an initialized `Mpv`.
+    pub fn log_level(mut self, level: LogLevel) -> MpvBuilder {
+        self.log_level = Some(level);
+        self
+    }
+
+    /// Load the config file at `path` before initialization, after the other options are set. The
+    /// path has to be absolute, and a file.
+    pub fn config_file(mut self, path: &str) -> MpvBuilder {
+        self.config_file = Some(path.to_owned());
+        self
+    }
+
+    /// Create and initialize the `Mpv`.
+    pub fn build(self) -> Result<Mpv> {
+        self.build_with_initializer(|_| Ok(()))
+    }
+
+    /// Create and initialize the `Mpv`, calling `initializer` after the options of this builder
+    /// are set.
+    pub fn build_with_initializer<F: FnOnce(MpvInitializer) -> Result<()>>(
+        self,
+        initializer: F,
+    ) -> Result<Mpv> {
+        let api_version = unsafe { libmpv_sys::mpv_client_api_version() };
+        if crate::MPV_CLIENT_API_MAJOR != api_version >> 16 {
+            return Err(Error::VersionMismatch {
+                linked: crate::MPV_CLIENT_API_VERSION,
+                loaded: api_version,
+            });
+        }
+
+        let ctx = unsafe { libmpv_sys::mpv_create() };
+        if ctx.is_null() {
+            return Err(Error::Null);
+        }
+
+        let state = Arc::new(CoreState::default());
+        let initialized = self
+            .apply(ctx, state.clone(), initializer)
+            .and_then(|_| mpv_err((), unsafe { libmpv_sys::mpv_initialize(ctx) }));
+        if let Err(err) = initialized {
+            unsafe { libmpv_sys::mpv_terminate_destroy(ctx) };
+            return Err(err);
+        }
+
+        let ctx = unsafe { NonNull::new_unchecked(ctx) };
+        Ok(Mpv::from_handle(
+            ctx,
+            Some(CoreRef::Strong(Arc::new(Core {
+                ctx,
+                detached: false,
+                state: state.clone(),
+            }))),
+            state,
+        ))
+    }
+
+    fn apply<F: FnOnce(MpvInitializer) -> Result<()>>(
+        self,
+        ctx: *mut libmpv_sys::mpv_handle,
+        state: Arc<CoreState>,
+        initializer: F,
+    ) -> Result<()> {
+        for (name, value) in &self.options {
+            let name = CString::new(name.as_str())?;
+            let value = CString::new(value.as_str())?;
+            mpv_err((), unsafe {
+                libmpv_sys::mpv_set_option_string(ctx, name.as_ptr(), value.as_ptr())
+            })?;
+        }
+
+        initializer(MpvInitializer { ctx, state })?;
+
+        if let Some(level) = self.log_level {
+            let level = CString::new(log_level_name(level)?)?;
+            mpv_err((), unsafe {
+                libmpv_sys::mpv_request_log_messages(ctx, level.as_ptr())
+            })?;
+        }
+
+        if let Some(path) = self.config_file {
+            let path = CString::new(path)?;
+            mpv_err((), unsafe {
+                libmpv_sys::mpv_load_config_file(ctx, path.as_ptr())
+            })?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use crate::events::{EventContext, Observer};
+use crate::properties::{CHAPTER, CHAPTER_LIST, EDITION, EDITION_LIST};
+use crate::*;
+
+use std::cmp::Ordering;
+use std::fmt::Write;
+
+/// A chapter of the current file, read from `chapter-list`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chapter {
+    pub title: Option<String>,
+    /// Start of the chapter in seconds.
+    pub time: f64,
+}
+
+impl Chapter {
+    /// Parse an entry of `chapter-list`.
+    pub fn from_node(node: &OwnedNode) -> Result<Chapter> {
+        Ok(Chapter {
+            title: node
+                .get("title")
+                .and_then(OwnedNode::to_str)
+                .map(str::to_owned),
+            time: node
+                .get("time")
+                .and_then(OwnedNode::to_f64)
+                .ok_or(Error::Raw(mpv_error::PropertyFormat))?,
+        })
+    }
+
+    /// Write `chapters` as an FFmpeg metadata document, the format read by the `chapters-file`
+    /// option. The chapters are sorted by time, and each one ends where the next one starts.
+    /// The last one ends at `duration` seconds of the file, or where it starts if the duration is
+    /// unknown, as mpv only reads the starts. Times are rounded to milliseconds.
+    pub fn to_ffmetadata(chapters: &[Chapter], duration: Option<f64>) -> String {
+        fn escape(value: &str) -> String {
+            let mut escaped = String::with_capacity(value.len());
+            for c in value.chars() {
+                if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+                    escaped.push('\\');
+                }
+                escaped.push(c);
+            }
+            escaped
+        }
+
+        let millis = |time: f64| (time * 1000.).round() as i64;
+
+        let mut sorted: Vec<&Chapter> = chapters.iter().collect();
+        sorted.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(Ordering::Equal));
+
+        let mut doc = String::from(";FFMETADATA1\n");
+        for (i, chapter) in sorted.iter().enumerate() {
+            let start = millis(chapter.time);
+            let end = match sorted.get(i + 1) {
+                Some(next) => millis(next.time),
+                None => duration.map_or(start, |duration| millis(duration).max(start)),
+            };
+            let _ = write!(
+                doc,
+                "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\n",
+                start, end
+            );
+            if let Some(ref title) = chapter.title {
+                let _ = writeln!(doc, "title={}", escape(title));
+            }
+        }
+        doc
+    }
+
+    /// The name and value of the per-file option that makes `chapters` the chapters of a file,
+    /// to be passed to `Mpv::playlist_load_file_with_options`. The chapters are passed in
+    /// memory, no file is written.
+    pub fn file_option(chapters: &[Chapter]) -> (&'static str, String) {
+        let url = format!("memory://{}", Chapter::to_ffmetadata(chapters, None));
+        ("chapters-file", url)
+    }
+}
+
+/// An edition of the current file, read from `edition-list`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Edition {
+    /// The id used to select the edition.
+    pub id: i64,
+    pub title: Option<String>,
+    /// Whether this edition is played if none is selected.
+    pub default: bool,
+}
+
+impl Edition {
+    /// Parse an entry of `edition-list`.
+    pub fn from_node(node: &OwnedNode) -> Result<Edition> {
+        Ok(Edition {
+            id: node
+                .get("id")
+                .and_then(OwnedNode::to_i64)
+                .ok_or(Error::Raw(mpv_error::PropertyFormat))?,
+            title: node
+                .get("title")
+                .and_then(OwnedNode::to_str)
+                .map(str::to_owned),
+            default: node
+                .get("default")
+                .and_then(OwnedNode::to_bool)
+                .unwrap_or(false),
+        })
+    }
+}
+
+fn list<T>(node: OwnedNode, from_node: fn(&OwnedNode) -> Result<T>) -> Result<Vec<T>> {
+    node.to_array()
+        .ok_or(Error::Raw(mpv_error::PropertyFormat))?
+        .iter()
+        .map(from_node)
+        .collect()
+}
+
+impl Mpv {
+    /// The chapters of the current file, ordered by time.
+    pub fn chapters(&self) -> Result<Vec<Chapter>> {
+        list(self.get(&CHAPTER_LIST)?, Chapter::from_node)
+    }
+
+    /// The index of the current chapter, `None` before the first chapter or if the current
+    /// file has no chapters.
+    pub fn current_chapter(&self) -> Result<Option<usize>> {
+        match self.get(&CHAPTER) {
+            Ok(chapter) if chapter >= 0 => Ok(Some(chapter as usize)),
+            Ok(_) | Err(Error::Raw(mpv_error::PropertyUnavailable)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Seek to the start of the chapter at `index` in `Mpv::chapters`.
+    pub fn goto_chapter(&self, index: usize) -> Result<()> {
+        self.set(&CHAPTER, index as i64)
+    }
+
+    /// Seek to the start of the next chapter.
+    pub fn next_chapter(&self) -> Result<()> {
+        self.command("add", &["chapter", "1"])
+    }
+
+    /// Seek to the start of the current chapter, or the previous one depending on the
+    /// `chapter-seek-threshold` option.
+    pub fn previous_chapter(&self) -> Result<()> {
+        self.command("add", &["chapter", "-1"])
+    }
+
+    /// Load the file at `path` like `Mpv::playlist_load_files`, with `chapters` replacing the
+    /// chapters of the file, see `Chapter::file_option`. Returns the id of the new entry.
+    pub fn playlist_load_file_with_chapters(
+        &self,
+        path: &str,
+        state: FileState,
+        chapters: &[Chapter],
+    ) -> Result<i64> {
+        let (name, value) = Chapter::file_option(chapters);
+        self.playlist_load_file_with_options(path, state, &[(name, &value)])
+    }
+
+    /// The editions of the current file.
+    pub fn editions(&self) -> Result<Vec<Edition>> {
+        list(self.get(&EDITION_LIST)?, Edition::from_node)
+    }
+
+    /// Play the edition with `id`, see `Edition::id`. This reloads the current file.
+    pub fn set_edition(&self, id: i64) -> Result<()> {
+        self.set(&EDITION, id)
+    }
+}
+
+impl EventContext {
+    /// Observe the index of the current chapter, see `Mpv::current_chapter`. The value is
+    /// `ObservedValue::Unavailable` if the current file has no chapters.
+    pub fn observe_chapter(&self) -> Result<Observer<i64>> {
+        self.observe_key(&CHAPTER)
+    }
+}
@@ -0,0 +1,238 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use super::{mpv_err, MpvInitializer};
+use crate::*;
+
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::rc::Rc;
+use std::sync::Mutex;
+
+/// The maximum nesting of profiles, as in mpv.
+const MAX_PROFILE_DEPTH: usize = 20;
+
+/// An option of a config, with the line it is defined at.
+pub(crate) struct ConfigOption {
+    line: usize,
+    name: String,
+    value: String,
+}
+
+/// The profiles defined by `load_config_str`, shared by all clients of a core.
+pub(crate) type Profiles = HashMap<String, Vec<ConfigOption>>;
+
+/// A config, split into the options of the default section, and the options of each profile
+/// section.
+#[derive(Default)]
+struct Config {
+    options: Vec<ConfigOption>,
+    profiles: Vec<(String, Vec<ConfigOption>)>,
+}
+
+fn config_err(line: usize, error: Error) -> Error {
+    Error::Config {
+        line,
+        error: Rc::new(error),
+    }
+}
+
+/// Returns whether `rest` of a line is empty or a comment.
+fn is_line_end(rest: &str) -> bool {
+    let rest = rest.trim_start();
+    rest.is_empty() || rest.starts_with('#')
+}
+
+/// Parse the value of an option, which may be quoted with `"` or `'`, or prefixed with its
+/// length as `%len%`. Unquoted values end at a comment.
+fn parse_value(value: &str) -> Option<&str> {
+    let (value, rest) = if value.starts_with('"') || value.starts_with('\'') {
+        let quote = &value[..1];
+        let end = value[1..].find(quote)? + 1;
+        (&value[1..end], &value[end + 1..])
+    } else if let Some(fixed) = value.strip_prefix('%') {
+        let (len, fixed) = fixed.split_at(fixed.find('%')?);
+        let len: usize = len.parse().ok()?;
+        let fixed = &fixed[1..];
+        if len > fixed.len() || !fixed.is_char_boundary(len) {
+            return None;
+        }
+        fixed.split_at(len)
+    } else {
+        let end = value.find('#').unwrap_or(value.len());
+        (value[..end].trim_end(), "")
+    };
+
+    if is_line_end(rest) {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// Parse `contents` with the syntax of `mpv.conf`.
+fn parse_config(contents: &str) -> Result<Config> {
+    let mut config = Config::default();
+    let mut in_profile = false;
+
+    for (i, line) in contents.lines().enumerate() {
+        let line_nr = i + 1;
+        let format_err = || config_err(line_nr, Error::Raw(mpv_error::OptionFormat));
+        let line = line.trim();
+
+        if let Some(header) = line.strip_prefix('[') {
+            let end = header.find(']').ok_or_else(format_err)?;
+            if !is_line_end(&header[end + 1..]) {
+                return Err(format_err());
+            }
+            in_profile = &header[..end] != "default";
+            if in_profile {
+                config.profiles.push((header[..end].to_owned(), Vec::new()));
+            }
+            continue;
+        }
+        if is_line_end(line) {
+            continue;
+        }
+
+        let line = line.strip_prefix("--").unwrap_or(line);
+        let name_end = line
+            .find(|c: char| c == '=' || c == '#' || c.is_whitespace())
+            .unwrap_or(line.len());
+        let (name, rest) = line.split_at(name_end);
+        let rest = rest.trim_start();
+
+        // Options without a value behave as on the command line, e.g. `no-opt`
+        let value = match rest.strip_prefix('=') {
+            Some(value) => parse_value(value.trim_start()).ok_or_else(format_err)?,
+            None if is_line_end(rest) => "",
+            None => return Err(format_err()),
+        };
+
+        let option = ConfigOption {
+            line: line_nr,
+            name: name.to_owned(),
+            value: value.to_owned(),
+        };
+        match config.profiles.last_mut() {
+            Some(_) if in_profile && name == "profile-desc" => {}
+            // Auto profiles are not supported, so they would silently never apply
+            Some(_) if in_profile && (name == "profile-cond" || name == "profile-restore") => {
+                return Err(config_err(line_nr, Error::Raw(mpv_error::NotImplemented)));
+            }
+            Some((_, options)) if in_profile => options.push(option),
+            _ => config.options.push(option),
+        }
+    }
+
+    Ok(config)
+}
+
+fn set_option(ctx: *mut libmpv_sys::mpv_handle, name: &str, value: &str) -> Result<()> {
+    let name = CString::new(name)?;
+    let value = CString::new(value)?;
+    mpv_err((), unsafe {
+        libmpv_sys::mpv_set_option_string(ctx, name.as_ptr(), value.as_ptr())
+    })
+}
+
+/// Set `options` in order, applying the profiles of `profile` options.
+fn set_options(
+    ctx: *mut libmpv_sys::mpv_handle,
+    profiles: &Profiles,
+    options: &[ConfigOption],
+    depth: usize,
+) -> Result<()> {
+    for option in options {
+        if option.name == "profile" {
+            for name in option.value.split(',') {
+                apply_profile(ctx, profiles, name, depth + 1)
+                    .map_err(|e| config_err(option.line, e))?;
+            }
+        } else {
+            set_option(ctx, &option.name, &option.value).map_err(|e| config_err(option.line, e))?;
+        }
+    }
+    Ok(())
+}
+
+fn apply_profile(
+    ctx: *mut libmpv_sys::mpv_handle,
+    profiles: &Profiles,
+    name: &str,
+    depth: usize,
+) -> Result<()> {
+    if depth > MAX_PROFILE_DEPTH {
+        return Err(Error::Raw(mpv_error::InvalidParameter));
+    }
+    match profiles.get(name) {
+        Some(options) => set_options(ctx, profiles, options, depth),
+        // Profiles of mpv itself, e.g. the built-in ones or those of `mpv.conf`
+        None => set_option(ctx, "profile", name),
+    }
+}
+
+fn load_config_str(
+    ctx: *mut libmpv_sys::mpv_handle,
+    profiles: &Mutex<Profiles>,
+    contents: &str,
+) -> Result<()> {
+    let config = parse_config(contents)?;
+
+    // mpv can only define profiles from config files, so they are kept here instead
+    let mut profiles = profiles.lock().unwrap();
+    for (name, options) in config.profiles {
+        profiles.entry(name).or_default().extend(options);
+    }
+    set_options(ctx, &profiles, &config.options, 0)
+}
+
+impl Mpv {
+    /// Load a configuration from `contents`, with the syntax of `mpv.conf`.
+    ///
+    /// Options of the default section are set in order, and the first option that can't be set
+    /// is returned as `Error::Config` with its line number, starting at `1`.
+    ///
+    /// Profile sections (`[name]`) are kept by this crate for all clients of the core, because
+    /// mpv can only define profiles from files. They are only applied by `Mpv::apply_profile`
+    /// and by `profile` options of configs loaded with this method. mpv itself doesn't know
+    /// them, so its `apply-profile` and `show-profile` commands, the `profile-list` property and
+    /// scripts can't use them. `profile-desc` is ignored, and auto profiles are not supported, so
+    /// `profile-cond` and `profile-restore` are returned as `Error::Config` with
+    /// `mpv_error::NotImplemented`.
+    pub fn load_config_str(&self, contents: &str) -> Result<()> {
+        load_config_str(self.ctx.as_ptr(), &self.client.state.profiles, contents)
+    }
+
+    /// Apply the profile `name`, that is either defined by `Mpv::load_config_str`, or by mpv
+    /// itself, e.g. a built-in profile or one of `mpv.conf`. Profiles of
+    /// `Mpv::load_config_str` take precedence over those of mpv with the same name. Options of profiles of
+    /// `Mpv::load_config_str` that can't be set are returned as `Error::Config` with their line
+    /// number.
+    pub fn apply_profile(&self, name: &str) -> Result<()> {
+        let profiles = self.client.state.profiles.lock().unwrap();
+        apply_profile(self.ctx.as_ptr(), &profiles, name, 0)
+    }
+}
+
+impl MpvInitializer {
+    /// Load a configuration from `contents` before initialization, see `Mpv::load_config_str`.
+    pub fn load_config_str(&self, contents: &str) -> Result<()> {
+        load_config_str(self.ctx, &self.state.profiles, contents)
+    }
+}
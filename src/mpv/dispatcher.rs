@@ -0,0 +1,337 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use crate::events::{Event, EventContext, EventMask, ObserveData, ObservedValue, PropertyData};
+use crate::properties::Property;
+use crate::{
+    mpv::{mpv_err, Client},
+    *,
+};
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::thread::{self, JoinHandle};
+
+type EventHandler = Arc<Mutex<dyn FnMut(&Event) + Send>>;
+type PropertyHandler = Arc<Mutex<dyn FnMut(&PropertyData) -> Result<()> + Send>>;
+type ErrorHandler = Arc<Mutex<dyn FnMut(&Error) + Send>>;
+
+#[derive(Default)]
+struct Handlers {
+    events: HashMap<u64, (EventMask, EventHandler)>,
+    properties: HashMap<u64, PropertyHandler>,
+    errors: HashMap<u64, ErrorHandler>,
+}
+
+struct Shared {
+    client: Arc<Client>,
+    stop: AtomicBool,
+    next_id: AtomicU64,
+    handlers: Mutex<Handlers>,
+    panic: Mutex<Option<Box<dyn Any + Send>>>,
+}
+
+/// Owns an `EventContext` and runs registered handlers for incoming events on a dedicated
+/// thread.
+///
+/// Handlers are called in no particular order, and are unregistered when the `HandlerGuard`
+/// returned on registration is dropped. Handlers may register or unregister other handlers,
+/// but should return quickly, as they block the dispatching of later events.
+///
+/// If a handler panics, dispatching stops, and the panic is resumed by `into_inner` or when the
+/// dispatcher is dropped.
+pub struct EventDispatcher {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<EventContext>>,
+}
+
+impl EventDispatcher {
+    /// Start dispatching the events of `ctx` on a new thread.
+    pub fn new(mut ctx: EventContext) -> EventDispatcher {
+        let shared = Arc::new(Shared {
+            client: ctx.client(),
+            stop: AtomicBool::new(false),
+            next_id: AtomicU64::new(0),
+            handlers: Mutex::new(Handlers::default()),
+            panic: Mutex::new(None),
+        });
+
+        let thread_shared = shared.clone();
+        let thread = thread::Builder::new()
+            .name("mpv-event-dispatcher".to_owned())
+            .spawn(move || {
+                while !thread_shared.stop.load(Ordering::Acquire) {
+                    let dispatched = match ctx.wait_event(-1.) {
+                        Some(Ok(ev)) => {
+                            let shutdown = matches!(ev, Event::Shutdown);
+                            panic::catch_unwind(AssertUnwindSafe(|| thread_shared.dispatch(&ev)))
+                                .map(|_| shutdown)
+                        }
+                        Some(Err(e)) => panic::catch_unwind(AssertUnwindSafe(|| {
+                            thread_shared.dispatch_error(&e)
+                        }))
+                        .map(|_| false),
+                        None => Ok(false),
+                    };
+                    match dispatched {
+                        Ok(false) => {}
+                        Ok(true) => break,
+                        Err(panic) => {
+                            *thread_shared
+                                .panic
+                                .lock()
+                                .unwrap_or_else(PoisonError::into_inner) = Some(panic);
+                            break;
+                        }
+                    }
+                }
+                ctx
+            })
+            .expect("failed to spawn event dispatcher thread");
+
+        EventDispatcher {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    /// Call `handler` for every event whose kind is in `kinds`.
+    pub fn on<M, F>(&self, kinds: M, handler: F) -> HandlerGuard
+    where
+        M: Into<EventMask>,
+        F: FnMut(&Event) + Send + 'static,
+    {
+        let id = self.shared.next_id();
+        self.shared
+            .handlers
+            .lock()
+            .unwrap()
+            .events
+            .insert(id, (kinds.into(), Arc::new(Mutex::new(handler))));
+        HandlerGuard::new(&self.shared, id, HandlerKind::Event)
+    }
+
+    /// Observe `property` as `T`, and call `handler` with every change. The property is
+    /// unobserved again when the returned guard is dropped. Changes that can't be converted to
+    /// `T` are passed to the `on_error` handlers instead.
+    pub fn on_property<T, F>(&self, property: &Property<T>, mut handler: F) -> Result<HandlerGuard>
+    where
+        T: ObserveData,
+        F: FnMut(ObservedValue<T>) + Send + 'static,
+    {
+        let name = CString::new(property.name())?;
+        let format = T::get_format();
+        // Allocated like the ids of `EventContext::observe`, so they don't collide with other
+        // observations of the client
+        let id = self
+            .shared
+            .client
+            .next_observer_id
+            .fetch_add(1, Ordering::Relaxed);
+
+        // Insert the handler first, so that no change is missed
+        let handler = move |change: &PropertyData| T::from_property_data(change).map(&mut handler);
+        self.shared
+            .handlers
+            .lock()
+            .unwrap()
+            .properties
+            .insert(id, Arc::new(Mutex::new(handler)));
+        let guard = HandlerGuard::new(&self.shared, id, HandlerKind::Property);
+
+        mpv_err((), unsafe {
+            libmpv_sys::mpv_observe_property(
+                self.shared.client.ctx.as_ptr(),
+                id,
+                name.as_ptr(),
+                format.as_mpv_format() as _,
+            )
+        })?;
+        Ok(guard)
+    }
+
+    /// Call `handler` for every error returned while waiting for events, e.g. a failed
+    /// asynchronous command.
+    pub fn on_error<F>(&self, handler: F) -> HandlerGuard
+    where
+        F: FnMut(&Error) + Send + 'static,
+    {
+        let id = self.shared.next_id();
+        self.shared
+            .handlers
+            .lock()
+            .unwrap()
+            .errors
+            .insert(id, Arc::new(Mutex::new(handler)));
+        HandlerGuard::new(&self.shared, id, HandlerKind::Error)
+    }
+
+    /// Stop dispatching events, and return the `EventContext`. Events that were not dispatched
+    /// yet remain in its queue.
+    ///
+    /// # Panics
+    /// If a handler panicked, which stopped the dispatching, its panic is resumed.
+    pub fn into_inner(mut self) -> EventContext {
+        let thread = self.thread.take().expect("the thread is only taken once");
+        match self.stop(thread) {
+            Ok(ctx) => ctx,
+            Err(panic) => panic::resume_unwind(panic),
+        }
+    }
+
+    // Stop the thread, and return the panic of a handler if one panicked
+    fn stop(&self, thread: JoinHandle<EventContext>) -> thread::Result<EventContext> {
+        self.shared.stop.store(true, Ordering::Release);
+        unsafe { libmpv_sys::mpv_wakeup(self.shared.client.ctx.as_ptr()) };
+        let ctx = thread.join()?;
+        let panic = self
+            .shared
+            .panic
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        match panic {
+            Some(panic) => Err(panic),
+            None => Ok(ctx),
+        }
+    }
+}
+
+impl Drop for EventDispatcher {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            if let Err(panic) = self.stop(thread) {
+                if !thread::panicking() {
+                    panic::resume_unwind(panic);
+                }
+            }
+        }
+    }
+}
+
+impl Shared {
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn dispatch(&self, ev: &Event) {
+        // Snapshot the handlers, so that they can (un)register handlers themselves
+        let (events, property) = {
+            let handlers = self.handlers.lock().unwrap();
+            let events: Vec<EventHandler> = match ev.kind() {
+                Some(kind) => handlers
+                    .events
+                    .values()
+                    .filter(|(mask, _)| mask.contains(kind))
+                    .map(|(_, handler)| handler.clone())
+                    .collect(),
+                None => Vec::new(),
+            };
+            let property = match *ev {
+                Event::PropertyChange { reply_userdata, .. } => {
+                    handlers.properties.get(&reply_userdata).cloned()
+                }
+                _ => None,
+            };
+            (events, property)
+        };
+
+        for handler in events {
+            (handler.lock().unwrap())(ev);
+        }
+        if let (Some(handler), Event::PropertyChange { change, .. }) = (property, ev) {
+            let ret = (handler.lock().unwrap())(change);
+            if let Err(e) = ret {
+                self.dispatch_error(&e);
+            }
+        }
+    }
+
+    fn dispatch_error(&self, err: &Error) {
+        let errors: Vec<ErrorHandler> = self
+            .handlers
+            .lock()
+            .unwrap()
+            .errors
+            .values()
+            .cloned()
+            .collect();
+        for handler in errors {
+            (handler.lock().unwrap())(err);
+        }
+    }
+}
+
+enum HandlerKind {
+    Event,
+    Property,
+    Error,
+}
+
+/// Unregisters its handler from the `EventDispatcher` when dropped. A property is unobserved
+/// even if the guard outlives the dispatcher.
+#[must_use = "the handler is unregistered when the guard is dropped"]
+pub struct HandlerGuard {
+    shared: Weak<Shared>,
+    client: Arc<Client>,
+    id: u64,
+    kind: HandlerKind,
+}
+
+impl HandlerGuard {
+    fn new(shared: &Arc<Shared>, id: u64, kind: HandlerKind) -> HandlerGuard {
+        HandlerGuard {
+            shared: Arc::downgrade(shared),
+            client: shared.client.clone(),
+            id,
+            kind,
+        }
+    }
+}
+
+impl Drop for HandlerGuard {
+    fn drop(&mut self) {
+        if let HandlerKind::Property = self.kind {
+            unsafe { libmpv_sys::mpv_unobserve_property(self.client.ctx.as_ptr(), self.id) };
+        }
+
+        let shared = match self.shared.upgrade() {
+            Some(shared) => shared,
+            None => return,
+        };
+        let mut handlers = shared
+            .handlers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match self.kind {
+            HandlerKind::Event => {
+                handlers.events.remove(&self.id);
+            }
+            HandlerKind::Property => {
+                handlers.properties.remove(&self.id);
+            }
+            HandlerKind::Error => {
+                handlers.errors.remove(&self.id);
+            }
+        }
+    }
+}
@@ -27,6 +27,16 @@ pub enum Error {
         index: usize,
         error: Rc<Error>,
     },
+    /// An option of `Mpv::load_config_str` could not be set, `line` starts at `1`.
+    Config {
+        line: usize,
+        error: Rc<Error>,
+    },
+    /// An argument of `MpvInitializer::apply_args` could not be applied.
+    Argument {
+        index: usize,
+        error: Rc<Error>,
+    },
     VersionMismatch {
         linked: ctype::c_ulong,
         loaded: ctype::c_ulong,
@@ -16,15 +16,26 @@
 // License along with this library; if not, write to the Free Software
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 
-use libmpv_sys::{mpv_event, mpv_set_wakeup_callback};
+use libmpv_sys::mpv_set_wakeup_callback;
 
-use crate::{mpv::mpv_err, *};
+use crate::{
+    mpv::{mpv_err, Client},
+    properties::{Property, AUDIO_OUT_PARAMS, AUDIO_PARAMS, VIDEO_OUT_PARAMS, VIDEO_PARAMS},
+    *,
+};
 
 use std::ffi::{c_void, CString};
+use std::fmt;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ops::BitOr;
 use std::os::raw as ctype;
-use std::ptr::NonNull;
+use std::ptr::{self, NonNull};
 use std::slice;
 use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// An `Event`'s ID.
 pub use libmpv_sys::mpv_event_id as EventId;
@@ -36,6 +47,7 @@ pub mod mpv_event_id {
     pub use libmpv_sys::mpv_event_id_MPV_EVENT_FILE_LOADED as FileLoaded;
     pub use libmpv_sys::mpv_event_id_MPV_EVENT_GET_PROPERTY_REPLY as GetPropertyReply;
     pub use libmpv_sys::mpv_event_id_MPV_EVENT_HOOK as Hook;
+    pub use libmpv_sys::mpv_event_id_MPV_EVENT_IDLE as Idle;
     pub use libmpv_sys::mpv_event_id_MPV_EVENT_LOG_MESSAGE as LogMessage;
     pub use libmpv_sys::mpv_event_id_MPV_EVENT_NONE as None;
     pub use libmpv_sys::mpv_event_id_MPV_EVENT_PLAYBACK_RESTART as PlaybackRestart;
@@ -49,21 +61,227 @@ pub mod mpv_event_id {
     pub use libmpv_sys::mpv_event_id_MPV_EVENT_VIDEO_RECONFIG as VideoReconfig;
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// The kind of an `Event`, i.e. every event known to this crate without its data.
+pub enum EventKind {
+    Shutdown,
+    LogMessage,
+    GetPropertyReply,
+    SetPropertyReply,
+    CommandReply,
+    StartFile,
+    EndFile,
+    FileLoaded,
+    Idle,
+    Tick,
+    ClientMessage,
+    VideoReconfig,
+    AudioReconfig,
+    Seek,
+    PlaybackRestart,
+    PropertyChange,
+    QueueOverflow,
+    Hook,
+}
+
+impl EventKind {
+    /// Every kind of event, in the order of their IDs.
+    pub const ALL: [EventKind; 18] = [
+        EventKind::Shutdown,
+        EventKind::LogMessage,
+        EventKind::GetPropertyReply,
+        EventKind::SetPropertyReply,
+        EventKind::CommandReply,
+        EventKind::StartFile,
+        EventKind::EndFile,
+        EventKind::FileLoaded,
+        EventKind::Idle,
+        EventKind::Tick,
+        EventKind::ClientMessage,
+        EventKind::VideoReconfig,
+        EventKind::AudioReconfig,
+        EventKind::Seek,
+        EventKind::PlaybackRestart,
+        EventKind::PropertyChange,
+        EventKind::QueueOverflow,
+        EventKind::Hook,
+    ];
+
+    /// The mpv ID of this kind of event.
+    pub fn id(self) -> EventId {
+        match self {
+            EventKind::Shutdown => mpv_event_id::Shutdown,
+            EventKind::LogMessage => mpv_event_id::LogMessage,
+            EventKind::GetPropertyReply => mpv_event_id::GetPropertyReply,
+            EventKind::SetPropertyReply => mpv_event_id::SetPropertyReply,
+            EventKind::CommandReply => mpv_event_id::CommandReply,
+            EventKind::StartFile => mpv_event_id::StartFile,
+            EventKind::EndFile => mpv_event_id::EndFile,
+            EventKind::FileLoaded => mpv_event_id::FileLoaded,
+            EventKind::Idle => mpv_event_id::Idle,
+            EventKind::Tick => mpv_event_id::Tick,
+            EventKind::ClientMessage => mpv_event_id::ClientMessage,
+            EventKind::VideoReconfig => mpv_event_id::VideoReconfig,
+            EventKind::AudioReconfig => mpv_event_id::AudioReconfig,
+            EventKind::Seek => mpv_event_id::Seek,
+            EventKind::PlaybackRestart => mpv_event_id::PlaybackRestart,
+            EventKind::PropertyChange => mpv_event_id::PropertyChange,
+            EventKind::QueueOverflow => mpv_event_id::QueueOverflow,
+            EventKind::Hook => mpv_event_id::Hook,
+        }
+    }
+
+    /// The kind of event with the mpv ID `id`, or `None` if it is unknown to this crate.
+    pub fn from_id(id: EventId) -> Option<EventKind> {
+        EventKind::ALL.iter().copied().find(|kind| kind.id() == id)
+    }
+
+    /// The symbolic name of this kind of event as given by mpv, e.g. `"start-file"`.
+    pub fn name(self) -> &'static str {
+        let name = unsafe { libmpv_sys::mpv_event_name(self.id()) };
+        if name.is_null() {
+            ""
+        } else {
+            // SAFETY: mpv guarantees that the name is static
+            unsafe { mpv_cstr_to_str!(name) }.unwrap_or("")
+        }
+    }
+
+    /// The kind of event with the symbolic `name`, see [name](#method.name).
+    pub fn from_name(name: &str) -> Option<EventKind> {
+        EventKind::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.name() == name)
+    }
+
+    /// Whether this event is deprecated by mpv, i.e. `Idle` and `Tick`.
+    pub fn is_deprecated(self) -> bool {
+        matches!(self, EventKind::Idle | EventKind::Tick)
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+/// A set of `EventKind`s, used to enable or disable many events at once.
+pub struct EventMask(pub(crate) u64);
+
+impl EventMask {
+    /// A mask without any events.
+    pub const fn empty() -> EventMask {
+        EventMask(0)
+    }
+
+    /// A mask with every event.
+    pub fn all() -> EventMask {
+        EventKind::ALL.iter().copied().collect()
+    }
+
+    /// A mask with every event, except deprecated ones.
+    pub fn all_but_deprecated() -> EventMask {
+        EventKind::ALL
+            .iter()
+            .copied()
+            .filter(|kind| !kind.is_deprecated())
+            .collect()
+    }
+
+    /// The events mpv enables for a new handle, i.e. all except `Tick`.
+    pub fn mpv_default() -> EventMask {
+        EventMask::all().without(EventKind::Tick)
+    }
+
+    /// Returns this mask with `kind` added.
+    pub fn with(mut self, kind: EventKind) -> EventMask {
+        self.insert(kind);
+        self
+    }
+
+    /// Returns this mask with `kind` removed.
+    pub fn without(mut self, kind: EventKind) -> EventMask {
+        self.remove(kind);
+        self
+    }
+
+    pub fn insert(&mut self, kind: EventKind) {
+        self.0 |= 1 << kind.id();
+    }
+
+    pub fn remove(&mut self, kind: EventKind) {
+        self.0 &= !(1 << kind.id());
+    }
+
+    pub fn contains(&self, kind: EventKind) -> bool {
+        self.0 & (1 << kind.id()) != 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Iterate over the events in this mask, in the order of their IDs.
+    pub fn iter(&self) -> impl Iterator<Item = EventKind> {
+        let mask = *self;
+        EventKind::ALL
+            .iter()
+            .copied()
+            .filter(move |&kind| mask.contains(kind))
+    }
+}
+
+impl From<EventKind> for EventMask {
+    fn from(kind: EventKind) -> EventMask {
+        EventMask::empty().with(kind)
+    }
+}
+
+impl FromIterator<EventKind> for EventMask {
+    fn from_iter<I: IntoIterator<Item = EventKind>>(iter: I) -> EventMask {
+        iter.into_iter().fold(EventMask::empty(), EventMask::with)
+    }
+}
+
+impl<T: Into<EventMask>> BitOr<T> for EventMask {
+    type Output = EventMask;
+
+    fn bitor(self, other: T) -> EventMask {
+        EventMask(self.0 | other.into().0)
+    }
+}
+
+impl<T: Into<EventMask>> BitOr<T> for EventKind {
+    type Output = EventMask;
+
+    fn bitor(self, other: T) -> EventMask {
+        EventMask::from(self) | other
+    }
+}
+
 impl Mpv {
     /// Create a context that can be used to wait for events and control which events are listened
     /// for.
     ///
+    /// Every client has its own events, see `Mpv::create_client`.
+    ///
+    /// The `EventContext` keeps the client alive, even if all clones of this `Mpv` are dropped.
+    /// Another context can be created after it is dropped.
+    ///
     /// # Panics
-    /// Panics if a context already exists
+    /// Panics if a context already exists for this client
     pub fn create_event_context(&self) -> EventContext {
-        match self
-            .events_guard
-            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
-        {
-            Ok(_) => EventContext {
-                ctx: self.ctx,
-                wakeup_callback_cleanup: None,
-            },
+        match self.client.events_guard.compare_exchange(
+            false,
+            true,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => EventContext::new(self.client.clone()),
             Err(_) => panic!("Event context already exists"),
         }
     }
@@ -78,12 +296,18 @@ pub enum PropertyData<'a> {
     Int64(i64),
     Double(ctype::c_double),
     Node(&'a MpvNode),
+    /// The property is unavailable, e.g. `PropertyChange` of `"media-title"` when no file is
+    /// loaded.
+    Unavailable,
 }
 
 impl<'a> PropertyData<'a> {
     // SAFETY: meant to extract the data from an event property. See `mpv_event_property` in
     // `client.h`
     unsafe fn from_raw(format: MpvFormat, ptr: *mut ctype::c_void) -> Result<PropertyData<'a>> {
+        if format == mpv_format::None {
+            return Ok(PropertyData::Unavailable);
+        }
         assert!(!ptr.is_null());
         match format {
             mpv_format::Flag => Ok(PropertyData::Flag(*(ptr as *mut bool))),
@@ -98,12 +322,85 @@ impl<'a> PropertyData<'a> {
             mpv_format::Double => Ok(PropertyData::Double(*(ptr as *mut f64))),
             mpv_format::Int64 => Ok(PropertyData::Int64(*(ptr as *mut i64))),
             mpv_format::Node => Ok(PropertyData::Node(&*(ptr as *mut MpvNode))),
-            mpv_format::None => unreachable!(),
             _ => unimplemented!(),
         }
     }
+
+    /// Copy the data, so that it no longer borrows from the event it came from.
+    pub fn to_owned(&self) -> Result<OwnedPropertyData> {
+        Ok(match *self {
+            PropertyData::Str(s) => OwnedPropertyData::Str(s.to_owned()),
+            PropertyData::OsdStr(s) => OwnedPropertyData::OsdStr(s.to_owned()),
+            PropertyData::Flag(b) => OwnedPropertyData::Flag(b),
+            PropertyData::Int64(i) => OwnedPropertyData::Int64(i),
+            PropertyData::Double(d) => OwnedPropertyData::Double(d),
+            PropertyData::Node(node) => OwnedPropertyData::Node(node.to_owned_node()?),
+            PropertyData::Unavailable => OwnedPropertyData::Unavailable,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// An owned copy of `PropertyData`.
+pub enum OwnedPropertyData {
+    Str(String),
+    OsdStr(String),
+    Flag(bool),
+    Int64(i64),
+    Double(ctype::c_double),
+    Node(OwnedNode),
+    Unavailable,
+}
+
+/// The value of an observed property.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ObservedValue<T> {
+    Value(T),
+    /// The property is unavailable, e.g. `"media-title"` when no file is loaded.
+    Unavailable,
+}
+
+impl<T> ObservedValue<T> {
+    /// The value, or `None` if the property is unavailable.
+    pub fn value(self) -> Option<T> {
+        match self {
+            ObservedValue::Value(value) => Some(value),
+            ObservedValue::Unavailable => None,
+        }
+    }
 }
 
+/// This trait describes which types can be observed with `EventContext::observe`. The property is
+/// observed in the format given by `GetData::get_format`.
+pub trait ObserveData: GetData {
+    /// Convert the data of a `PropertyChange` event, that was observed in the format of `Self`.
+    ///
+    /// Returns `Error::Raw(mpv_error::PropertyFormat)` if `data` has a different format.
+    fn from_property_data(data: &PropertyData) -> Result<ObservedValue<Self>>;
+}
+
+macro_rules! impl_observe_data {
+    ($ty: ty, $variant: ident, $conv: expr) => {
+        impl ObserveData for $ty {
+            fn from_property_data(data: &PropertyData) -> Result<ObservedValue<$ty>> {
+                match *data {
+                    PropertyData::$variant(value) => Ok(ObservedValue::Value($conv(value)?)),
+                    PropertyData::Unavailable => Ok(ObservedValue::Unavailable),
+                    _ => Err(Error::Raw(mpv_error::PropertyFormat)),
+                }
+            }
+        }
+    };
+}
+
+impl_observe_data!(bool, Flag, Ok::<_, Error>);
+impl_observe_data!(i64, Int64, Ok::<_, Error>);
+impl_observe_data!(f64, Double, Ok::<_, Error>);
+impl_observe_data!(String, Str, |s: &str| Ok::<_, Error>(s.to_owned()));
+impl_observe_data!(OwnedNode, Node, MpvNode::to_owned_node);
+
 #[derive(Debug)]
 pub enum Event<'a> {
     /// Received when the player is shutting down
@@ -123,17 +420,55 @@ pub enum Event<'a> {
     },
     /// Received when using set_property_async
     SetPropertyReply(u64),
-    /// Received when using command_async
-    CommandReply(u64),
+    /// Received when using command_async, `result` holds the data returned by the command
+    CommandReply {
+        reply_userdata: u64,
+        result: &'a MpvNode,
+    },
     /// Event received when a new file is playing
-    StartFile,
+    StartFile {
+        /// Playlist entry ID of the file being loaded now
+        playlist_entry_id: i64,
+    },
     /// Event received when the file being played currently has stopped, for an error or not
-    EndFile(EndFileReason),
+    EndFile {
+        reason: EndFileReason,
+        /// The mpv error that ended playback, if `reason` is `mpv_end_file_reason::Error`
+        error: Option<MpvError>,
+        /// Playlist entry ID of the file that was being played or attempted to be played
+        playlist_entry_id: i64,
+        /// If the entry was replaced by other entries, e.g. because it was a playlist, the
+        /// playlist entry ID of the first inserted entry
+        playlist_insert_id: i64,
+        /// The number of inserted playlist entries, see `playlist_insert_id`
+        playlist_insert_num_entries: i32,
+    },
     /// Event received when a file has been *loaded*, but has not been started
     FileLoaded,
+    /// Event received when the player entered idle mode, i.e. no file is played
+    Idle,
+    /// Sent every time after a video frame is displayed, disabled by default
+    Tick,
     ClientMessage(Vec<&'a str>),
-    VideoReconfig,
-    AudioReconfig,
+    /// The video output was reconfigured, e.g. because the video size changed.
+    ///
+    /// The parameters are only read if enabled with `EventContext::set_reconfig_params`, and are
+    /// `None` otherwise, or if they are not available, e.g. because video was disabled. They are
+    /// a snapshot read when the event is received, so they may already reflect a later
+    /// reconfiguration.
+    VideoReconfig {
+        /// The new value of `video-params`
+        params: Option<Box<VideoParams>>,
+        /// The new value of `video-out-params`
+        out_params: Option<Box<VideoParams>>,
+    },
+    /// The audio output was reconfigured, see `VideoReconfig`.
+    AudioReconfig {
+        /// The new value of `audio-params`
+        params: Option<Box<AudioParams>>,
+        /// The new value of `audio-out-params`
+        out_params: Option<Box<AudioParams>>,
+    },
     /// The player changed current position
     Seek,
     PlaybackRestart,
@@ -145,10 +480,227 @@ pub enum Event<'a> {
     },
     /// Received when the Event Queue is full
     QueueOverflow,
-    /// A deprecated event
-    Deprecated(mpv_event),
+    /// Received when a hook registered with `hook_add` is run. The player is blocked until
+    /// `hook_continue` is called with `id`.
+    Hook {
+        name: &'a str,
+        id: u64,
+        reply_userdata: u64,
+    },
+    /// An event that is unknown to this version of the crate, and should be ignored
+    Unknown(EventId),
 }
 
+impl Event<'_> {
+    /// The kind of this event, or `None` if it is `Event::Unknown`.
+    pub fn kind(&self) -> Option<EventKind> {
+        Some(match *self {
+            Event::Shutdown => EventKind::Shutdown,
+            Event::LogMessage { .. } => EventKind::LogMessage,
+            Event::GetPropertyReply { .. } => EventKind::GetPropertyReply,
+            Event::SetPropertyReply(_) => EventKind::SetPropertyReply,
+            Event::CommandReply { .. } => EventKind::CommandReply,
+            Event::StartFile { .. } => EventKind::StartFile,
+            Event::EndFile { .. } => EventKind::EndFile,
+            Event::FileLoaded => EventKind::FileLoaded,
+            Event::Idle => EventKind::Idle,
+            Event::Tick => EventKind::Tick,
+            Event::ClientMessage(_) => EventKind::ClientMessage,
+            Event::VideoReconfig { .. } => EventKind::VideoReconfig,
+            Event::AudioReconfig { .. } => EventKind::AudioReconfig,
+            Event::Seek => EventKind::Seek,
+            Event::PlaybackRestart => EventKind::PlaybackRestart,
+            Event::PropertyChange { .. } => EventKind::PropertyChange,
+            Event::QueueOverflow => EventKind::QueueOverflow,
+            Event::Hook { .. } => EventKind::Hook,
+            Event::Unknown(_) => return None,
+        })
+    }
+
+    /// Copy all data of this event, so that it can outlive the next call to `wait_event`, e.g.
+    /// to send it to another thread.
+    ///
+    /// Returns `Err` if a contained node has invalid utf-8 or an unsupported format.
+    pub fn to_owned(&self) -> Result<OwnedEvent> {
+        Ok(match *self {
+            Event::Shutdown => OwnedEvent::Shutdown,
+            Event::LogMessage {
+                prefix,
+                level,
+                text,
+                log_level,
+            } => OwnedEvent::LogMessage {
+                prefix: prefix.to_owned(),
+                level: level.to_owned(),
+                text: text.to_owned(),
+                log_level,
+            },
+            Event::GetPropertyReply {
+                name,
+                ref result,
+                reply_userdata,
+            } => OwnedEvent::GetPropertyReply {
+                name: name.to_owned(),
+                result: result.to_owned()?,
+                reply_userdata,
+            },
+            Event::SetPropertyReply(u) => OwnedEvent::SetPropertyReply(u),
+            Event::CommandReply {
+                reply_userdata,
+                result,
+            } => OwnedEvent::CommandReply {
+                reply_userdata,
+                result: result.to_owned_node()?,
+            },
+            Event::StartFile { playlist_entry_id } => OwnedEvent::StartFile { playlist_entry_id },
+            Event::EndFile {
+                reason,
+                error,
+                playlist_entry_id,
+                playlist_insert_id,
+                playlist_insert_num_entries,
+            } => OwnedEvent::EndFile {
+                reason,
+                error,
+                playlist_entry_id,
+                playlist_insert_id,
+                playlist_insert_num_entries,
+            },
+            Event::FileLoaded => OwnedEvent::FileLoaded,
+            Event::Idle => OwnedEvent::Idle,
+            Event::Tick => OwnedEvent::Tick,
+            Event::ClientMessage(ref messages) => {
+                OwnedEvent::ClientMessage(messages.iter().map(|&m| m.to_owned()).collect())
+            }
+            Event::VideoReconfig {
+                ref params,
+                ref out_params,
+            } => OwnedEvent::VideoReconfig {
+                params: params.clone(),
+                out_params: out_params.clone(),
+            },
+            Event::AudioReconfig {
+                ref params,
+                ref out_params,
+            } => OwnedEvent::AudioReconfig {
+                params: params.clone(),
+                out_params: out_params.clone(),
+            },
+            Event::Seek => OwnedEvent::Seek,
+            Event::PlaybackRestart => OwnedEvent::PlaybackRestart,
+            Event::PropertyChange {
+                name,
+                ref change,
+                reply_userdata,
+            } => OwnedEvent::PropertyChange {
+                name: name.to_owned(),
+                change: change.to_owned()?,
+                reply_userdata,
+            },
+            Event::QueueOverflow => OwnedEvent::QueueOverflow,
+            Event::Hook {
+                name,
+                id,
+                reply_userdata,
+            } => OwnedEvent::Hook {
+                name: name.to_owned(),
+                id,
+                reply_userdata,
+            },
+            Event::Unknown(id) => OwnedEvent::Unknown(id),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// An owned copy of an `Event`, created by `Event::to_owned`. See `Event` for the meaning of the
+/// variants.
+pub enum OwnedEvent {
+    Shutdown,
+    LogMessage {
+        prefix: String,
+        level: String,
+        text: String,
+        log_level: LogLevel,
+    },
+    GetPropertyReply {
+        name: String,
+        result: OwnedPropertyData,
+        reply_userdata: u64,
+    },
+    SetPropertyReply(u64),
+    CommandReply {
+        reply_userdata: u64,
+        result: OwnedNode,
+    },
+    StartFile {
+        playlist_entry_id: i64,
+    },
+    EndFile {
+        reason: EndFileReason,
+        error: Option<MpvError>,
+        playlist_entry_id: i64,
+        playlist_insert_id: i64,
+        playlist_insert_num_entries: i32,
+    },
+    FileLoaded,
+    Idle,
+    Tick,
+    ClientMessage(Vec<String>),
+    VideoReconfig {
+        params: Option<Box<VideoParams>>,
+        out_params: Option<Box<VideoParams>>,
+    },
+    AudioReconfig {
+        params: Option<Box<AudioParams>>,
+        out_params: Option<Box<AudioParams>>,
+    },
+    Seek,
+    PlaybackRestart,
+    PropertyChange {
+        name: String,
+        change: OwnedPropertyData,
+        reply_userdata: u64,
+    },
+    QueueOverflow,
+    Hook {
+        name: String,
+        id: u64,
+        reply_userdata: u64,
+    },
+    Unknown(EventId),
+}
+
+impl OwnedEvent {
+    /// The kind of this event, or `None` if it is `OwnedEvent::Unknown`.
+    pub fn kind(&self) -> Option<EventKind> {
+        Some(match *self {
+            OwnedEvent::Shutdown => EventKind::Shutdown,
+            OwnedEvent::LogMessage { .. } => EventKind::LogMessage,
+            OwnedEvent::GetPropertyReply { .. } => EventKind::GetPropertyReply,
+            OwnedEvent::SetPropertyReply(_) => EventKind::SetPropertyReply,
+            OwnedEvent::CommandReply { .. } => EventKind::CommandReply,
+            OwnedEvent::StartFile { .. } => EventKind::StartFile,
+            OwnedEvent::EndFile { .. } => EventKind::EndFile,
+            OwnedEvent::FileLoaded => EventKind::FileLoaded,
+            OwnedEvent::Idle => EventKind::Idle,
+            OwnedEvent::Tick => EventKind::Tick,
+            OwnedEvent::ClientMessage(_) => EventKind::ClientMessage,
+            OwnedEvent::VideoReconfig { .. } => EventKind::VideoReconfig,
+            OwnedEvent::AudioReconfig { .. } => EventKind::AudioReconfig,
+            OwnedEvent::Seek => EventKind::Seek,
+            OwnedEvent::PlaybackRestart => EventKind::PlaybackRestart,
+            OwnedEvent::PropertyChange { .. } => EventKind::PropertyChange,
+            OwnedEvent::QueueOverflow => EventKind::QueueOverflow,
+            OwnedEvent::Hook { .. } => EventKind::Hook,
+            OwnedEvent::Unknown(_) => return None,
+        })
+    }
+}
+
+pub(crate) const OBSERVER_IDS_START: u64 = 1 << 63;
+
 unsafe extern "C" fn wu_wrapper<F: Fn() + Send + 'static>(ctx: *mut c_void) {
     if ctx.is_null() {
         panic!("ctx for wakeup wrapper is NULL");
@@ -157,56 +709,173 @@ unsafe extern "C" fn wu_wrapper<F: Fn() + Send + 'static>(ctx: *mut c_void) {
     (*(ctx as *mut F))();
 }
 
+/// Observes a property for as long as it lives, created by `EventContext::observe`.
+///
+/// Changes are received as `Event::PropertyChange` from the `EventContext`, and can be converted
+/// to `T` with [change](#method.change).
+pub struct Observer<T: ObserveData> {
+    client: Arc<Client>,
+    id: u64,
+    name: String,
+    _format: PhantomData<fn() -> T>,
+}
+
+unsafe impl<T: ObserveData> Send for Observer<T> {}
+unsafe impl<T: ObserveData> Sync for Observer<T> {}
+
+impl<T: ObserveData> Observer<T> {
+    /// The `reply_userdata` of this observer's `PropertyChange` events.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The name of the observed property.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the new value if `event` is a change of this observer's property, otherwise
+    /// `None`.
+    pub fn change(&self, event: &Event) -> Option<Result<ObservedValue<T>>> {
+        match *event {
+            Event::PropertyChange {
+                ref change,
+                reply_userdata,
+                ..
+            } if reply_userdata == self.id => Some(T::from_property_data(change)),
+            _ => None,
+        }
+    }
+}
+
+impl<T: ObserveData> Drop for Observer<T> {
+    fn drop(&mut self) {
+        unsafe { libmpv_sys::mpv_unobserve_property(self.client.ctx.as_ptr(), self.id) };
+    }
+}
+
+/// Observes a group of properties with the same id for as long as it lives, created by
+/// `EventContext::observe_group`.
+pub struct ObserverGroup {
+    client: Arc<Client>,
+    id: u64,
+}
+
+impl ObserverGroup {
+    /// The `reply_userdata` of the `PropertyChange` events of this group.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Drop for ObserverGroup {
+    fn drop(&mut self) {
+        unsafe { libmpv_sys::mpv_unobserve_property(self.client.ctx.as_ptr(), self.id) };
+    }
+}
+
+/// The end of a timeout in seconds, for waiting on several events. A negative timeout never
+/// ends.
+pub(crate) struct Deadline(Option<Instant>);
+
+impl Deadline {
+    pub(crate) fn new(timeout: f64) -> Deadline {
+        if timeout < 0. {
+            Deadline(None)
+        } else {
+            Deadline(Some(Instant::now() + Duration::from_secs_f64(timeout)))
+        }
+    }
+
+    /// The timeout to pass to `wait_event`, which is `-1` if the deadline never ends.
+    pub(crate) fn remaining(&self) -> f64 {
+        self.0.map_or(-1., |deadline| {
+            deadline
+                .saturating_duration_since(Instant::now())
+                .as_secs_f64()
+        })
+    }
+
+    pub(crate) fn expired(&self) -> bool {
+        matches!(self.0, Some(deadline) if Instant::now() >= deadline)
+    }
+}
+
 /// Context to listen to events.
 pub struct EventContext {
     ctx: NonNull<libmpv_sys::mpv_handle>,
+    client: Arc<Client>,
     wakeup_callback_cleanup: Option<Box<dyn FnOnce()>>,
+    reconfig_params: bool,
 }
 
 unsafe impl Send for EventContext {}
 
 impl EventContext {
-    pub(crate) fn new(ctx: NonNull<libmpv_sys::mpv_handle>) -> Self {
+    pub(crate) fn new(client: Arc<Client>) -> Self {
         EventContext {
-            ctx,
+            ctx: client.ctx,
+            client,
             wakeup_callback_cleanup: None,
+            reconfig_params: false,
         }
     }
 
-    /// Enable an event.
-    pub fn enable_event(&self, ev: events::EventId) -> Result<()> {
+    pub(crate) fn client(&self) -> Arc<Client> {
+        self.client.clone()
+    }
+
+    fn request_event(&self, kind: EventKind, enable: bool) -> Result<()> {
         mpv_err((), unsafe {
-            libmpv_sys::mpv_request_event(self.ctx.as_ptr(), ev, 1)
-        })
+            libmpv_sys::mpv_request_event(self.ctx.as_ptr(), kind.id(), enable as _)
+        })?;
+        let bit = EventMask::from(kind).0;
+        if enable {
+            self.client.enabled_events.fetch_or(bit, Ordering::AcqRel);
+        } else {
+            self.client.enabled_events.fetch_and(!bit, Ordering::AcqRel);
+        }
+        Ok(())
+    }
+
+    /// Enable an event.
+    pub fn enable_event(&self, kind: EventKind) -> Result<()> {
+        self.request_event(kind, true)
+    }
+
+    /// Enable every event in `mask`. Events not in `mask` are left unchanged.
+    pub fn enable_events(&self, mask: EventMask) -> Result<()> {
+        mask.iter().try_for_each(|kind| self.enable_event(kind))
     }
 
     /// Enable all, except deprecated, events.
     pub fn enable_all_events(&self) -> Result<()> {
-        for i in (2..9).chain(16..19).chain(20..23).chain(24..26) {
-            self.enable_event(i)?;
-        }
-        Ok(())
+        self.enable_events(EventMask::all_but_deprecated())
     }
 
-    /// Disable an event.
-    pub fn disable_event(&self, ev: events::EventId) -> Result<()> {
-        mpv_err((), unsafe {
-            libmpv_sys::mpv_request_event(self.ctx.as_ptr(), ev, 0)
-        })
+    /// Disable an event. `EventKind::Shutdown` can't be disabled.
+    pub fn disable_event(&self, kind: EventKind) -> Result<()> {
+        self.request_event(kind, false)
+    }
+
+    /// Disable every event in `mask`. Events not in `mask` are left unchanged.
+    pub fn disable_events(&self, mask: EventMask) -> Result<()> {
+        mask.iter().try_for_each(|kind| self.disable_event(kind))
     }
 
-    /// Diable all deprecated events.
+    /// Disable all deprecated events.
     pub fn disable_deprecated_events(&self) -> Result<()> {
-        self.disable_event(libmpv_sys::mpv_event_id_MPV_EVENT_IDLE)?;
-        Ok(())
+        self.disable_events(EventKind::Idle | EventKind::Tick)
     }
 
-    /// Diable all events.
+    /// Disable all events, except `EventKind::Shutdown` which can't be disabled.
     pub fn disable_all_events(&self) -> Result<()> {
-        for i in 2..26 {
-            self.disable_event(i as _)?;
-        }
-        Ok(())
+        self.disable_events(EventMask::all().without(EventKind::Shutdown))
+    }
+
+    /// The events that are currently enabled for the client of this context.
+    pub fn enabled_events(&self) -> EventMask {
+        EventMask(self.client.enabled_events.load(Ordering::Acquire))
     }
 
     /// Observe `name` property for changes. `id` can be used to unobserve this (or many) properties
@@ -223,6 +892,40 @@ impl EventContext {
         })
     }
 
+    /// Observe the property `name` for changes as `T`, until the returned `Observer` is dropped.
+    ///
+    /// The id of the observer is allocated from `2^63` upwards, so it does not collide with
+    /// smaller ids passed to [observe_property](#method.observe_property).
+    pub fn observe<T: ObserveData>(&self, name: &str) -> Result<Observer<T>> {
+        let id = self.client.next_observer_id.fetch_add(1, Ordering::Relaxed);
+        self.observe_property(name, T::get_format(), id)?;
+        Ok(Observer {
+            client: self.client.clone(),
+            id,
+            name: name.to_owned(),
+            _format: PhantomData,
+        })
+    }
+
+    /// Observe the typed `property` for changes, like [observe](#method.observe).
+    pub fn observe_key<T: ObserveData>(&self, property: &Property<T>) -> Result<Observer<T>> {
+        self.observe(property.name())
+    }
+
+    /// Observe all `properties` in their format with one id, until the returned `ObserverGroup`
+    /// is dropped. The id is allocated like in [observe](#method.observe).
+    pub fn observe_group(&self, properties: &[(&str, Format)]) -> Result<ObserverGroup> {
+        let id = self.client.next_observer_id.fetch_add(1, Ordering::Relaxed);
+        let group = ObserverGroup {
+            client: self.client.clone(),
+            id,
+        };
+        for &(name, format) in properties {
+            self.observe_property(name, format, id)?;
+        }
+        Ok(group)
+    }
+
     /// Unobserve any property associated with `id`.
     pub fn unobserve_property(&self, id: u64) -> Result<()> {
         mpv_err((), unsafe {
@@ -235,11 +938,26 @@ impl EventContext {
     ///
     /// This function is intended to be called repeatedly in a wait-event loop.
     ///
+    /// `VideoReconfig` and `AudioReconfig` events only carry parameters if enabled with
+    /// [set_reconfig_params](#method.set_reconfig_params).
+    ///
     /// Returns `Some(Err(...))` if there was invalid utf-8, or if either an
     /// `MPV_EVENT_GET_PROPERTY_REPLY`, `MPV_EVENT_SET_PROPERTY_REPLY`, `MPV_EVENT_COMMAND_REPLY`,
-    /// or `MPV_EVENT_PROPERTY_CHANGE` event failed, or if `MPV_EVENT_END_FILE` reported an error.
+    /// or `MPV_EVENT_PROPERTY_CHANGE` event failed. Errors of `MPV_EVENT_END_FILE` are reported
+    /// in `Event::EndFile`.
     pub fn wait_event(&mut self, timeout: f64) -> Option<Result<Event>> {
-        let event = unsafe { *libmpv_sys::mpv_wait_event(self.ctx.as_ptr(), timeout) };
+        let event = self.wait_raw_event(timeout);
+        self.convert_event(event)
+    }
+
+    /// Wait for an event like [wait_event](#method.wait_event), without converting it. The data
+    /// of the event is valid until the next call to `mpv_wait_event`.
+    pub(crate) fn wait_raw_event(&mut self, timeout: f64) -> libmpv_sys::mpv_event {
+        unsafe { *libmpv_sys::mpv_wait_event(self.ctx.as_ptr(), timeout) }
+    }
+
+    /// Convert an event returned by `wait_raw_event`, see [wait_event](#method.wait_event).
+    pub(crate) fn convert_event(&self, event: libmpv_sys::mpv_event) -> Option<Result<Event<'_>>> {
         if event.event_id != mpv_event_id::None {
             if let Err(e) = mpv_err((), event.error) {
                 return Some(Err(e));
@@ -282,21 +1000,45 @@ impl EventContext {
                 Event::SetPropertyReply(event.reply_userdata),
                 event.error,
             )),
-            mpv_event_id::CommandReply => Some(mpv_err(
-                Event::CommandReply(event.reply_userdata),
-                event.error,
-            )),
-            mpv_event_id::StartFile => Some(Ok(Event::StartFile)),
+            mpv_event_id::CommandReply => {
+                let command = event.data as *mut libmpv_sys::mpv_event_command;
+                // SAFETY: `MpvNode` is a transparent wrapper, and the node lives until the next
+                // call to `wait_event`
+                let result = unsafe { &*(&(*command).result as *const _ as *const MpvNode) };
+
+                Some(mpv_err(
+                    Event::CommandReply {
+                        reply_userdata: event.reply_userdata,
+                        result,
+                    },
+                    event.error,
+                ))
+            }
+            mpv_event_id::StartFile => {
+                let start_file = unsafe { *(event.data as *mut libmpv_sys::mpv_event_start_file) };
+
+                Some(Ok(Event::StartFile {
+                    playlist_entry_id: start_file.playlist_entry_id,
+                }))
+            }
             mpv_event_id::EndFile => {
                 let end_file = unsafe { *(event.data as *mut libmpv_sys::mpv_event_end_file) };
 
-                if let Err(e) = mpv_err((), end_file.error) {
-                    Some(Err(e))
-                } else {
-                    Some(Ok(Event::EndFile(end_file.reason as _)))
-                }
+                Some(Ok(Event::EndFile {
+                    reason: end_file.reason as _,
+                    error: if end_file.error < 0 {
+                        Some(end_file.error)
+                    } else {
+                        None
+                    },
+                    playlist_entry_id: end_file.playlist_entry_id,
+                    playlist_insert_id: end_file.playlist_insert_id,
+                    playlist_insert_num_entries: end_file.playlist_insert_num_entries,
+                }))
             }
             mpv_event_id::FileLoaded => Some(Ok(Event::FileLoaded)),
+            mpv_event_id::Idle => Some(Ok(Event::Idle)),
+            mpv_event_id::Tick => Some(Ok(Event::Tick)),
             mpv_event_id::ClientMessage => {
                 let client_message =
                     unsafe { *(event.data as *mut libmpv_sys::mpv_event_client_message) };
@@ -311,35 +1053,128 @@ impl EventContext {
                         .unwrap(),
                 )))
             }
-            mpv_event_id::VideoReconfig => Some(Ok(Event::VideoReconfig)),
-            mpv_event_id::AudioReconfig => Some(Ok(Event::AudioReconfig)),
+            mpv_event_id::VideoReconfig => Some(Ok(Event::VideoReconfig {
+                params: self.reconfig_params(&VIDEO_PARAMS),
+                out_params: self.reconfig_params(&VIDEO_OUT_PARAMS),
+            })),
+            mpv_event_id::AudioReconfig => Some(Ok(Event::AudioReconfig {
+                params: self.reconfig_params(&AUDIO_PARAMS),
+                out_params: self.reconfig_params(&AUDIO_OUT_PARAMS),
+            })),
             mpv_event_id::Seek => Some(Ok(Event::Seek)),
             mpv_event_id::PlaybackRestart => Some(Ok(Event::PlaybackRestart)),
             mpv_event_id::PropertyChange => {
                 let property = unsafe { *(event.data as *mut libmpv_sys::mpv_event_property) };
 
-                // This happens if the property is not available. For example,
+                // The format is `None` if the property is not available. For example,
                 // if you reached EndFile while observing a property.
-                if property.format == mpv_format::None {
-                    None
-                } else {
-                    let name = unsafe { mpv_cstr_to_str!(property.name) };
-                    Some(name.and_then(|name| {
-                        // SAFETY: safe because we are passing format + data from an mpv_event_property
-                        let change =
-                            unsafe { PropertyData::from_raw(property.format, property.data) }?;
-
-                        Ok(Event::PropertyChange {
-                            name,
-                            change,
-                            reply_userdata: event.reply_userdata,
-                        })
-                    }))
-                }
+                let name = unsafe { mpv_cstr_to_str!(property.name) };
+                Some(name.and_then(|name| {
+                    // SAFETY: safe because we are passing format + data from an mpv_event_property
+                    let change = unsafe { PropertyData::from_raw(property.format, property.data) }?;
+
+                    Ok(Event::PropertyChange {
+                        name,
+                        change,
+                        reply_userdata: event.reply_userdata,
+                    })
+                }))
             }
             mpv_event_id::QueueOverflow => Some(Ok(Event::QueueOverflow)),
-            _ => Some(Ok(Event::Deprecated(event))),
+            mpv_event_id::Hook => {
+                let hook = unsafe { *(event.data as *mut libmpv_sys::mpv_event_hook) };
+
+                let name = unsafe { mpv_cstr_to_str!(hook.name) };
+                Some(name.map(|name| Event::Hook {
+                    name,
+                    id: hook.id,
+                    reply_userdata: event.reply_userdata,
+                }))
+            }
+            id => Some(Ok(Event::Unknown(id))),
+        }
+    }
+
+    /// Whether `VideoReconfig` and `AudioReconfig` events carry the new parameters, which is
+    /// disabled by default. Reading them costs two synchronous `mpv_get_property` calls for
+    /// every such event.
+    pub fn set_reconfig_params(&mut self, enable: bool) {
+        self.reconfig_params = enable;
+    }
+
+    // Reconfig events carry no data, so the new parameters are read right away. They are `None`
+    // if they can't be read, e.g. because there is no video.
+    fn reconfig_params<T: GetData>(&self, property: &Property<T>) -> Option<Box<T>> {
+        if !self.reconfig_params {
+            return None;
+        }
+        let name = CString::new(property.name()).ok()?;
+        let format = T::get_format().as_mpv_format() as _;
+        T::get_from_c_void(|ptr| {
+            mpv_err((), unsafe {
+                libmpv_sys::mpv_get_property(self.ctx.as_ptr(), name.as_ptr(), format, ptr)
+            })
+        })
+        .ok()
+        .map(Box::new)
+    }
+
+    /// Wait until `Event::Shutdown` is received, discarding all other events. Returns `false` if
+    /// it was not received within `timeout` seconds, a negative `timeout` waits indefinitely.
+    ///
+    /// The client should be dropped after the shutdown, so that the core can terminate.
+    pub fn wait_for_shutdown(&mut self, timeout: f64) -> bool {
+        let deadline = Deadline::new(timeout);
+        loop {
+            if let Some(Ok(Event::Shutdown)) = self.wait_event(deadline.remaining()) {
+                return true;
+            }
+            if deadline.expired() {
+                return false;
+            }
+        }
+    }
+
+    /// Like [wait_event](#method.wait_event), but converts the event with `mpv_event_to_node`
+    /// into a generic node representation, instead of an `Event`. The node is a map that
+    /// contains at least the `event` key with the event name, see the mpv docs of
+    /// `mpv_event_to_node` for the other keys.
+    pub fn wait_event_node(&mut self, timeout: f64) -> Option<Result<OwnedNode>> {
+        let event = unsafe { libmpv_sys::mpv_wait_event(self.ctx.as_ptr(), timeout) };
+        if unsafe { (*event).event_id } == mpv_event_id::None {
+            return None;
         }
+
+        let mut node = MaybeUninit::uninit();
+        if let Err(e) = mpv_err((), unsafe {
+            libmpv_sys::mpv_event_to_node(node.as_mut_ptr(), event)
+        }) {
+            return Some(Err(e));
+        }
+        let node = super::MpvNode(unsafe { node.assume_init() });
+        Some(node.to_owned_node())
+    }
+
+    /// Register a hook handler for the hook `name`, e.g. `on_load`, see
+    /// [the manual](https://mpv.io/manual/master/#hooks). Every time the hook runs, an
+    /// `Event::Hook` with `reply_userdata` is received, and the player is blocked until
+    /// [hook_continue](#method.hook_continue) is called. Hooks with higher `priority` are run
+    /// first, the default priority is 0.
+    ///
+    /// Hooks can't be removed, they only go away with the handle they were registered with.
+    pub fn hook_add(&self, name: &str, priority: i32, reply_userdata: u64) -> Result<()> {
+        let name = CString::new(name)?;
+        mpv_err((), unsafe {
+            libmpv_sys::mpv_hook_add(self.ctx.as_ptr(), reply_userdata, name.as_ptr(), priority)
+        })
+    }
+
+    /// Let the player continue after handling the `Event::Hook` with the given `id`. This has to
+    /// be called exactly once for every hook event.
+    pub fn hook_continue(&self, id: u64) -> Result<()> {
+        mpv_err((), unsafe {
+            libmpv_sys::mpv_hook_continue(self.ctx.as_ptr(), id)
+        })
     }
 
     /// Set a custom function that should be called when there are new events. Use this if
@@ -370,13 +1205,7 @@ impl EventContext {
     ///
     /// Only one wakeup callback can be set.
     pub fn set_wakeup_callback<F: Fn() + Send + 'static>(&mut self, callback: F) {
-        if let Some(wakeup_callback_cleanup) = self.wakeup_callback_cleanup.take() {
-            wakeup_callback_cleanup();
-        }
         let raw_callback = Box::into_raw(Box::new(callback));
-        self.wakeup_callback_cleanup = Some(Box::new(move || unsafe {
-            Box::from_raw(raw_callback);
-        }) as Box<dyn FnOnce()>);
         unsafe {
             mpv_set_wakeup_callback(
                 self.ctx.as_ptr(),
@@ -384,13 +1213,25 @@ impl EventContext {
                 raw_callback as *mut c_void,
             );
         }
+        // The previous callback is freed after it was replaced, so mpv can't call it anymore
+        let previous = self
+            .wakeup_callback_cleanup
+            .replace(Box::new(move || unsafe {
+                Box::from_raw(raw_callback);
+            }) as Box<dyn FnOnce()>);
+        if let Some(wakeup_callback_cleanup) = previous {
+            wakeup_callback_cleanup();
+        }
     }
 }
 
 impl Drop for EventContext {
     fn drop(&mut self) {
         if let Some(wakeup_callback_cleanup) = self.wakeup_callback_cleanup.take() {
+            // The client may outlive this context, so unset the callback before freeing it
+            unsafe { mpv_set_wakeup_callback(self.ctx.as_ptr(), None, ptr::null_mut()) };
             wakeup_callback_cleanup();
         }
+        self.client.events_guard.store(false, Ordering::Release);
     }
 }
@@ -0,0 +1,207 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use crate::*;
+
+/// Convert an array of strings, as returned by e.g. `property-list`.
+pub(crate) fn string_list(node: &OwnedNode) -> Result<Vec<String>> {
+    node.to_array()
+        .ok_or(Error::Raw(mpv_error::PropertyFormat))?
+        .iter()
+        .map(|s| {
+            s.to_str()
+                .map(str::to_owned)
+                .ok_or(Error::Raw(mpv_error::PropertyFormat))
+        })
+        .collect()
+}
+
+/// The type of an option, as named by `option-info/<name>/type`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OptionType {
+    Flag,
+    Integer,
+    Integer64,
+    ByteSize,
+    Float,
+    Double,
+    Time,
+    String,
+    StringList,
+    KeyValueList,
+    Choice,
+    /// Any other type, with its name as reported by mpv.
+    Other(String),
+}
+
+impl OptionType {
+    fn from_name(name: &str) -> OptionType {
+        match name {
+            "Flag" => OptionType::Flag,
+            "Integer" => OptionType::Integer,
+            "Integer64" => OptionType::Integer64,
+            "ByteSize" => OptionType::ByteSize,
+            "Float" => OptionType::Float,
+            "Double" => OptionType::Double,
+            "Time" => OptionType::Time,
+            "String" => OptionType::String,
+            "String list" => OptionType::StringList,
+            "Key/value list" => OptionType::KeyValueList,
+            "Choice" => OptionType::Choice,
+            other => OptionType::Other(other.to_owned()),
+        }
+    }
+
+    /// The format in which values of this type are natively set and returned.
+    pub fn format(&self) -> Format {
+        match *self {
+            OptionType::Flag => Format::Flag,
+            OptionType::Integer | OptionType::Integer64 | OptionType::ByteSize => Format::Int64,
+            OptionType::Float | OptionType::Double | OptionType::Time => Format::Double,
+            OptionType::StringList | OptionType::KeyValueList => Format::Node,
+            OptionType::String | OptionType::Choice | OptionType::Other(_) => Format::String,
+        }
+    }
+}
+
+/// Information about an option, read from `option-info/<name>`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptionInfo {
+    pub name: String,
+    pub option_type: OptionType,
+    /// Whether the option was set from the command line or `MpvInitializer`, which is also the
+    /// case if it was set by a config file.
+    pub set_from_commandline: bool,
+    /// Whether the option was set per-file, e.g. with `file-local-options`.
+    pub set_locally: bool,
+    pub default_value: OwnedNode,
+    /// The minimum of numeric options, if any.
+    pub min: Option<f64>,
+    /// The maximum of numeric options, if any.
+    pub max: Option<f64>,
+    /// The allowed values of `OptionType::Choice` options.
+    pub choices: Vec<String>,
+}
+
+impl OptionInfo {
+    /// Parse the node of `option-info/<name>`.
+    pub fn from_node(node: &OwnedNode) -> Result<OptionInfo> {
+        let str_of = |key| {
+            node.get(key)
+                .and_then(OwnedNode::to_str)
+                .ok_or(Error::Raw(mpv_error::PropertyFormat))
+        };
+        let flag_of = |key| node.get(key).and_then(OwnedNode::to_bool).unwrap_or(false);
+
+        Ok(OptionInfo {
+            name: str_of("name")?.to_owned(),
+            option_type: OptionType::from_name(str_of("type")?),
+            set_from_commandline: flag_of("set-from-commandline"),
+            set_locally: flag_of("set-locally"),
+            default_value: node
+                .get("default-value")
+                .cloned()
+                .unwrap_or(OwnedNode::None),
+            min: node.get("min").and_then(OwnedNode::to_f64),
+            max: node.get("max").and_then(OwnedNode::to_f64),
+            choices: match node.get("choices") {
+                Some(choices) => string_list(choices)?,
+                None => Vec::new(),
+            },
+        })
+    }
+
+    /// Check whether `value` can be set for this option, without setting it. Strings are parsed
+    /// as on the command line for flags, numbers and choices, values of other types are only
+    /// checked for their format.
+    ///
+    /// Returns `Error::Raw(mpv_error::OptionFormat)` if the value has the wrong format, and
+    /// `Error::Raw(mpv_error::OptionError)` if it is out of range, or not a valid choice.
+    pub fn check_value(&self, value: &OwnedNode) -> Result<()> {
+        let format_err = Error::Raw(mpv_error::OptionFormat);
+        let in_range = |n: f64| {
+            let below = matches!(self.min, Some(min) if n < min);
+            let above = matches!(self.max, Some(max) if n > max);
+            if below || above {
+                Err(Error::Raw(mpv_error::OptionError))
+            } else {
+                Ok(())
+            }
+        };
+
+        match (&self.option_type, value) {
+            (OptionType::Flag, OwnedNode::Flag(_)) => Ok(()),
+            (OptionType::Flag, OwnedNode::String(s)) if s == "yes" || s == "no" => Ok(()),
+            (OptionType::Integer, _) | (OptionType::Integer64, _) => match *value {
+                OwnedNode::Int64(n) => in_range(n as f64),
+                OwnedNode::String(ref s) => {
+                    in_range(s.parse::<i64>().map_err(|_| format_err)? as _)
+                }
+                _ => Err(format_err),
+            },
+            (OptionType::Float, _) | (OptionType::Double, _) => match *value {
+                OwnedNode::Int64(_) | OwnedNode::Double(_) => in_range(value.to_f64().unwrap()),
+                OwnedNode::String(ref s) => in_range(s.parse().map_err(|_| format_err)?),
+                _ => Err(format_err),
+            },
+            (OptionType::Choice, OwnedNode::String(s)) => {
+                if self.choices.iter().any(|c| c == s) {
+                    Ok(())
+                } else if let Ok(n) = s.parse::<i64>() {
+                    // Some choices also accept numbers in a range
+                    match (self.min, self.max) {
+                        (Some(_), Some(_)) => in_range(n as f64),
+                        _ => Err(Error::Raw(mpv_error::OptionError)),
+                    }
+                } else {
+                    Err(Error::Raw(mpv_error::OptionError))
+                }
+            }
+            (OptionType::Choice, OwnedNode::Int64(n)) => match (self.min, self.max) {
+                (Some(_), Some(_)) => in_range(*n as f64),
+                _ => Err(format_err),
+            },
+            (OptionType::String, OwnedNode::String(_)) => Ok(()),
+            (OptionType::StringList, OwnedNode::Array(_))
+            | (OptionType::StringList, OwnedNode::String(_))
+            | (OptionType::KeyValueList, OwnedNode::Map(_))
+            | (OptionType::KeyValueList, OwnedNode::String(_)) => Ok(()),
+            (OptionType::ByteSize, _) | (OptionType::Time, _) | (OptionType::Other(_), _) => Ok(()),
+            _ => Err(format_err),
+        }
+    }
+}
+
+impl Mpv {
+    /// The names of all properties, from `property-list`.
+    pub fn property_list(&self) -> Result<Vec<String>> {
+        string_list(&self.get_property("property-list")?)
+    }
+
+    /// The names of all options, from `options`.
+    pub fn option_list(&self) -> Result<Vec<String>> {
+        string_list(&self.get_property("options")?)
+    }
+
+    /// Information about the option `name`, from `option-info/<name>`.
+    pub fn option_info(&self, name: &str) -> Result<OptionInfo> {
+        OptionInfo::from_node(&self.get_property(&format!("option-info/{}", name))?)
+    }
+}
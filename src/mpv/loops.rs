@@ -0,0 +1,397 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use crate::events::{Event, EventContext, ObserverGroup, PropertyData};
+use crate::properties::{AB_LOOP_A, AB_LOOP_B, AB_LOOP_COUNT, LOOP_FILE, LOOP_PLAYLIST};
+use crate::*;
+
+/// How often to loop, the value of `loop-file`, `loop-playlist` and `ab-loop-count`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Loop {
+    /// Don't loop.
+    No,
+    /// Loop this many more times.
+    Times(u32),
+    /// Loop forever.
+    Inf,
+    /// Like `Inf`, but also loop if every entry failed to play. Only valid for `loop-playlist`.
+    Force,
+}
+
+impl Loop {
+    fn parse(value: &str) -> Result<Loop> {
+        Ok(match value {
+            "no" | "0" => Loop::No,
+            "inf" | "yes" => Loop::Inf,
+            "force" => Loop::Force,
+            times => Loop::Times(
+                times
+                    .parse()
+                    .map_err(|_| Error::Raw(mpv_error::PropertyFormat))?,
+            ),
+        })
+    }
+
+    fn value(self) -> String {
+        match self {
+            Loop::No => "no".to_owned(),
+            Loop::Times(times) => times.to_string(),
+            Loop::Inf => "inf".to_owned(),
+            Loop::Force => "force".to_owned(),
+        }
+    }
+}
+
+/// Parse an A-B loop point, `"no"` if it is not set.
+fn loop_point(value: &str) -> Result<Option<f64>> {
+    match value {
+        "no" => Ok(None),
+        time => time
+            .parse()
+            .map(Some)
+            .map_err(|_| Error::Raw(mpv_error::PropertyFormat)),
+    }
+}
+
+impl Mpv {
+    /// The A-B loop points in seconds, `None` if a point is not set.
+    pub fn ab_loop(&self) -> Result<(Option<f64>, Option<f64>)> {
+        Ok((
+            loop_point(&self.get(&AB_LOOP_A)?)?,
+            loop_point(&self.get(&AB_LOOP_B)?)?,
+        ))
+    }
+
+    /// Loop playback between `from` and `to` seconds.
+    pub fn ab_loop_set(&self, from: f64, to: f64) -> Result<()> {
+        self.set(&AB_LOOP_A, from.to_string())?;
+        self.set(&AB_LOOP_B, to.to_string())
+    }
+
+    /// Stop the A-B loop.
+    pub fn ab_loop_clear(&self) -> Result<()> {
+        self.set(&AB_LOOP_A, "no".to_owned())?;
+        self.set(&AB_LOOP_B, "no".to_owned())
+    }
+
+    /// How often the A-B loop is repeated. mpv counts the remaining iterations down in the
+    /// `remaining-ab-loops` property, and resets them when this is set.
+    pub fn ab_loop_count(&self) -> Result<Loop> {
+        Loop::parse(&self.get(&AB_LOOP_COUNT)?)
+    }
+
+    /// Set how often the A-B loop is repeated. Fails with
+    /// `Error::Raw(mpv_error::InvalidParameter)` for `Loop::Force`.
+    pub fn set_ab_loop_count(&self, count: Loop) -> Result<()> {
+        if count == Loop::Force {
+            return Err(Error::Raw(mpv_error::InvalidParameter));
+        }
+        self.set(&AB_LOOP_COUNT, count.value())
+    }
+
+    /// How often the current file is looped.
+    pub fn loop_file(&self) -> Result<Loop> {
+        Loop::parse(&self.get(&LOOP_FILE)?)
+    }
+
+    /// Set how often the current file is looped. Fails with
+    /// `Error::Raw(mpv_error::InvalidParameter)` for `Loop::Force`.
+    pub fn set_loop_file(&self, count: Loop) -> Result<()> {
+        if count == Loop::Force {
+            return Err(Error::Raw(mpv_error::InvalidParameter));
+        }
+        self.set(&LOOP_FILE, count.value())
+    }
+
+    /// How often the playlist is looped.
+    pub fn loop_playlist(&self) -> Result<Loop> {
+        Loop::parse(&self.get(&LOOP_PLAYLIST)?)
+    }
+
+    /// Set how often the playlist is looped.
+    pub fn set_loop_playlist(&self, count: Loop) -> Result<()> {
+        self.set(&LOOP_PLAYLIST, count.value())
+    }
+}
+
+/// Which loop wrapped around, returned by `LoopObserver::wrapped`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LoopWrap {
+    /// Playback reached the B point, and jumped back to the A point.
+    AbLoop,
+    /// The current file ended, and started again because of `loop-file`.
+    File,
+    /// The last entry of the playlist ended, and the first one started because of
+    /// `loop-playlist`.
+    Playlist,
+}
+
+/// Detects when a loop wraps around, created by `EventContext::observe_loops`.
+///
+/// mpv has no event for this, so wraps are inferred from events and observed properties:
+///
+/// * If `ab-loop-count` or `loop-file` is a finite count, an A-B loop or `loop-file` wrap is a
+///   decrement of `remaining-ab-loops` or `remaining-file-loops`, which only mpv does when it
+///   wraps. Seeks by the user are never reported as wraps.
+/// * Otherwise, or with mpv versions without these properties, it is a `Seek` while the last
+///   `time-pos` is within `LoopObserver::TOLERANCE` seconds, multiplied by `speed` if that is
+///   faster, of the B point or the end of the file, after which playback restarts within the
+///   tolerance of the A point or the start of the file. A seek by the user between these
+///   positions is reported as a wrap too, and loops shorter than twice the tolerance are not
+///   detected, because every seek in them would look like a wrap.
+/// * A playlist wrap is the `StartFile` after the last entry ended with `EndFileReason::Eof`
+///   while `loop-playlist` is enabled.
+pub struct LoopObserver {
+    group: ObserverGroup,
+    time_pos: Option<f64>,
+    duration: Option<f64>,
+    speed: f64,
+    ab_loop: (Option<f64>, Option<f64>),
+    ab_loop_count: Loop,
+    loop_file: Loop,
+    loop_playlist: bool,
+    // `remaining-ab-loops` and `remaining-file-loops`, `None` if infinite or unavailable
+    remaining_ab_loops: Option<i64>,
+    remaining_file_loops: Option<i64>,
+    playlist_pos: Option<i64>,
+    playlist_count: i64,
+    // The wrap and loop start of the last seek if it may be a wrap, and whether playback
+    // restarted after it
+    seek: Option<(Option<(LoopWrap, f64)>, bool)>,
+    playlist_ended: bool,
+}
+
+impl LoopObserver {
+    /// How close in seconds `time-pos` has to be to the end of a loop before a seek, and to the
+    /// start of the loop after it, for the seek to be a wrap of a loop without a finite count.
+    pub const TOLERANCE: f64 = 0.5;
+
+    /// The `reply_userdata` of the `PropertyChange` events of this observer.
+    pub fn id(&self) -> u64 {
+        self.group.id()
+    }
+
+    /// Update the state of the observer with `event`, and return which loop wrapped around if
+    /// it did so.
+    pub fn wrapped(&mut self, event: &Event) -> Option<LoopWrap> {
+        match *event {
+            Event::Seek => {
+                self.seek = Some((self.seek_wrap(), false));
+                None
+            }
+            Event::PlaybackRestart => {
+                if let Some((_, ref mut restarted)) = self.seek {
+                    *restarted = true;
+                }
+                None
+            }
+            Event::EndFile { reason, .. } => {
+                self.playlist_ended = reason == mpv_end_file_reason::Eof
+                    && self.loop_playlist
+                    && self.playlist_pos == Some(self.playlist_count - 1);
+                None
+            }
+            Event::StartFile { .. } => {
+                self.seek = None;
+                self.time_pos = None;
+                if std::mem::replace(&mut self.playlist_ended, false) {
+                    Some(LoopWrap::Playlist)
+                } else {
+                    None
+                }
+            }
+            Event::PropertyChange {
+                name,
+                ref change,
+                reply_userdata,
+            } if reply_userdata == self.group.id() => self.property_changed(name, change),
+            _ => None,
+        }
+    }
+
+    // The wrap a seek from the current position would be, and where its loop starts
+    fn seek_wrap(&self) -> Option<(LoopWrap, f64)> {
+        let pos = self.time_pos?;
+        // `time-pos` changes are coalesced, so at higher speeds the last one before the seek
+        // may be further from the end of the loop
+        let tolerance = Self::TOLERANCE * self.speed.max(1.);
+        if let (Some(a), Some(b)) = self.ab_loop {
+            if self.remaining_ab_loops.is_none() && b - a > 2. * tolerance && pos >= b - tolerance {
+                return Some((LoopWrap::AbLoop, a));
+            }
+        }
+        match self.duration {
+            Some(duration)
+                if self.loop_file != Loop::No
+                    && self.remaining_file_loops.is_none()
+                    && duration > 2. * tolerance
+                    && pos >= duration - tolerance =>
+            {
+                Some((LoopWrap::File, 0.))
+            }
+            _ => None,
+        }
+    }
+
+    fn property_changed(&mut self, name: &str, change: &PropertyData) -> Option<LoopWrap> {
+        match (name, change) {
+            ("time-pos", &PropertyData::Double(pos)) => {
+                self.time_pos = Some(pos);
+                match self.seek {
+                    // Positions before the restart may still be from before the seek
+                    Some((wrap, true)) => {
+                        self.seek = None;
+                        let (wrap, start) = wrap?;
+                        if (pos - start).abs() <= Self::TOLERANCE {
+                            Some(wrap)
+                        } else {
+                            None
+                        }
+                    }
+                    _ => None,
+                }
+            }
+            ("time-pos", _) => {
+                self.time_pos = None;
+                None
+            }
+            ("duration", &PropertyData::Double(duration)) => {
+                self.duration = Some(duration);
+                None
+            }
+            ("duration", _) => {
+                self.duration = None;
+                None
+            }
+            ("ab-loop-a", &PropertyData::Str(value)) => {
+                self.ab_loop.0 = loop_point(value).ok()?;
+                None
+            }
+            ("ab-loop-b", &PropertyData::Str(value)) => {
+                self.ab_loop.1 = loop_point(value).ok()?;
+                None
+            }
+            ("speed", &PropertyData::Double(speed)) => {
+                self.speed = speed;
+                None
+            }
+            ("ab-loop-count", &PropertyData::Str(value)) => {
+                self.ab_loop_count = Loop::parse(value).ok()?;
+                None
+            }
+            ("loop-file", &PropertyData::Str(value)) => {
+                self.loop_file = Loop::parse(value).ok()?;
+                None
+            }
+            ("remaining-ab-loops", change) => {
+                let remaining = remaining_loops(change);
+                let old = std::mem::replace(&mut self.remaining_ab_loops, remaining);
+                if counted_wrap(old, remaining, self.ab_loop_count) {
+                    Some(LoopWrap::AbLoop)
+                } else {
+                    None
+                }
+            }
+            ("remaining-file-loops", change) => {
+                let remaining = remaining_loops(change);
+                let old = std::mem::replace(&mut self.remaining_file_loops, remaining);
+                if counted_wrap(old, remaining, self.loop_file) {
+                    Some(LoopWrap::File)
+                } else {
+                    None
+                }
+            }
+            ("loop-playlist", &PropertyData::Str(value)) => {
+                self.loop_playlist = !matches!(Loop::parse(value), Ok(Loop::No) | Err(_));
+                None
+            }
+            ("playlist-pos", &PropertyData::Int64(pos)) => {
+                self.playlist_pos = if pos < 0 { None } else { Some(pos) };
+                None
+            }
+            ("playlist-count", &PropertyData::Int64(count)) => {
+                self.playlist_count = count;
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+// The value of `remaining-ab-loops` or `remaining-file-loops` if it is finite
+fn remaining_loops(change: &PropertyData) -> Option<i64> {
+    match *change {
+        PropertyData::Int64(remaining) if remaining >= 0 => Some(remaining),
+        _ => None,
+    }
+}
+
+// Whether a change of a remaining loop count from `old` to `new` is a wrap, and not a reset
+// because the user set the option to `count`
+fn counted_wrap(old: Option<i64>, new: Option<i64>, count: Loop) -> bool {
+    let reset = match count {
+        Loop::No => new == Some(0),
+        Loop::Times(times) => new == Some(i64::from(times)),
+        Loop::Inf | Loop::Force => false,
+    };
+    match (old, new) {
+        (Some(old), Some(new)) => new < old && !reset,
+        _ => false,
+    }
+}
+
+impl EventContext {
+    /// Observe the playback position, playlist position and loop settings, to detect when a
+    /// loop wraps around.
+    pub fn observe_loops(&self) -> Result<LoopObserver> {
+        // mpv reports changes in the order of observation, so a change of an option that resets
+        // its remaining count is seen before the reset
+        let group = self.observe_group(&[
+            ("speed", Format::Double),
+            ("ab-loop-a", Format::String),
+            ("ab-loop-b", Format::String),
+            ("ab-loop-count", Format::String),
+            ("loop-file", Format::String),
+            ("loop-playlist", Format::String),
+            ("remaining-ab-loops", Format::Int64),
+            ("remaining-file-loops", Format::Int64),
+            ("playlist-pos", Format::Int64),
+            ("playlist-count", Format::Int64),
+            ("duration", Format::Double),
+            ("time-pos", Format::Double),
+        ])?;
+        Ok(LoopObserver {
+            group,
+            time_pos: None,
+            duration: None,
+            speed: 1.,
+            ab_loop: (None, None),
+            ab_loop_count: Loop::Inf,
+            loop_file: Loop::No,
+            loop_playlist: false,
+            remaining_ab_loops: None,
+            remaining_file_loops: None,
+            playlist_pos: None,
+            playlist_count: 0,
+            seek: None,
+            playlist_ended: false,
+        })
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use crate::properties::{AUDIO_OUT_PARAMS, AUDIO_PARAMS, VIDEO_OUT_PARAMS, VIDEO_PARAMS};
+use crate::*;
+
+/// Parameters of video frames, read from `video-params` or `video-out-params`. Fields are only
+/// present if known.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VideoParams {
+    /// The pixel format, e.g. `"yuv420p"`.
+    pub pixelformat: Option<String>,
+    /// The underlying pixel format, if `pixelformat` is a hardware format.
+    pub hw_pixelformat: Option<String>,
+    /// Width in pixels.
+    pub w: Option<i64>,
+    /// Height in pixels.
+    pub h: Option<i64>,
+    /// Display width, i.e. `w` corrected for the pixel aspect ratio.
+    pub dw: Option<i64>,
+    /// Display height, i.e. `h` corrected for the pixel aspect ratio.
+    pub dh: Option<i64>,
+    /// Display aspect ratio.
+    pub aspect: Option<f64>,
+    /// Pixel aspect ratio.
+    pub par: Option<f64>,
+    /// The colormatrix, e.g. `"bt.709"`.
+    pub colormatrix: Option<String>,
+    /// The color levels, `"limited"` or `"full"`.
+    pub colorlevels: Option<String>,
+    pub primaries: Option<String>,
+    /// The transfer characteristics, e.g. `"bt.1886"`.
+    pub gamma: Option<String>,
+    /// Clockwise rotation in degrees.
+    pub rotate: Option<i64>,
+}
+
+impl VideoParams {
+    /// Parse the value of `video-params` or `video-out-params`.
+    pub fn from_node(node: &OwnedNode) -> Result<VideoParams> {
+        if node.to_map().is_none() {
+            return Err(Error::Raw(mpv_error::PropertyFormat));
+        }
+        let i64_of = |key| node.get(key).and_then(OwnedNode::to_i64);
+        let f64_of = |key| node.get(key).and_then(OwnedNode::to_f64);
+        let str_of = |key| node.get(key).and_then(OwnedNode::to_str).map(str::to_owned);
+
+        Ok(VideoParams {
+            pixelformat: str_of("pixelformat"),
+            hw_pixelformat: str_of("hw-pixelformat"),
+            w: i64_of("w"),
+            h: i64_of("h"),
+            dw: i64_of("dw"),
+            dh: i64_of("dh"),
+            aspect: f64_of("aspect"),
+            par: f64_of("par"),
+            colormatrix: str_of("colormatrix"),
+            colorlevels: str_of("colorlevels"),
+            primaries: str_of("primaries"),
+            gamma: str_of("gamma"),
+            rotate: i64_of("rotate"),
+        })
+    }
+}
+
+/// Parameters of audio, read from `audio-params` or `audio-out-params`. Fields are only present
+/// if known.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AudioParams {
+    /// The sample format, e.g. `"floatp"`.
+    pub format: Option<String>,
+    /// Sample rate in Hz.
+    pub samplerate: Option<i64>,
+    /// The channel layout, e.g. `"stereo"` or `"5.1"`.
+    pub channels: Option<String>,
+    /// The channel layout as mpv would print it, e.g. `"5.1(side)"`.
+    pub hr_channels: Option<String>,
+    pub channel_count: Option<i64>,
+}
+
+impl AudioParams {
+    /// Parse the value of `audio-params` or `audio-out-params`.
+    pub fn from_node(node: &OwnedNode) -> Result<AudioParams> {
+        if node.to_map().is_none() {
+            return Err(Error::Raw(mpv_error::PropertyFormat));
+        }
+        let str_of = |key| node.get(key).and_then(OwnedNode::to_str).map(str::to_owned);
+
+        Ok(AudioParams {
+            format: str_of("format"),
+            samplerate: node.get("samplerate").and_then(OwnedNode::to_i64),
+            channels: str_of("channels"),
+            hr_channels: str_of("hr-channels"),
+            channel_count: node.get("channel-count").and_then(OwnedNode::to_i64),
+        })
+    }
+}
+
+impl_node_data!(VideoParams, VideoParams::from_node);
+impl_node_data!(AudioParams, AudioParams::from_node);
+
+impl Mpv {
+    /// The parameters of the decoded video, before filters.
+    pub fn video_params(&self) -> Result<VideoParams> {
+        self.get(&VIDEO_PARAMS)
+    }
+
+    /// The parameters of the video as passed to the video output, after filters.
+    pub fn video_out_params(&self) -> Result<VideoParams> {
+        self.get(&VIDEO_OUT_PARAMS)
+    }
+
+    /// The parameters of the decoded audio, before filters.
+    pub fn audio_params(&self) -> Result<AudioParams> {
+        self.get(&AUDIO_PARAMS)
+    }
+
+    /// The parameters of the audio as passed to the audio output, after filters.
+    pub fn audio_out_params(&self) -> Result<AudioParams> {
+        self.get(&AUDIO_OUT_PARAMS)
+    }
+}
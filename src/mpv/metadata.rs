@@ -0,0 +1,111 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use crate::properties::{CHAPTER_METADATA, FILTERED_METADATA, METADATA};
+use crate::*;
+
+/// Metadata tags, e.g. of `metadata`. Keys are compared case-insensitively, as their case
+/// depends on the file format.
+///
+/// Observe e.g. `properties::METADATA` to be notified when tags change, which happens for
+/// streams that update their ICY title.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Metadata {
+    /// The tags, in the order mpv returned them.
+    pub tags: Vec<(String, String)>,
+}
+
+impl Metadata {
+    /// Parse a map of tags.
+    pub fn from_node(node: &OwnedNode) -> Result<Metadata> {
+        let format_err = || Error::Raw(mpv_error::PropertyFormat);
+        Ok(Metadata {
+            tags: node
+                .to_map()
+                .ok_or_else(format_err)?
+                .iter()
+                .map(|(key, value)| {
+                    let value = value.to_str().ok_or_else(format_err)?;
+                    Ok((key.clone(), value.to_owned()))
+                })
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// The value of the tag `key`, ignoring case.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.get("title")
+    }
+
+    pub fn artist(&self) -> Option<&str> {
+        self.get("artist")
+    }
+
+    pub fn album(&self) -> Option<&str> {
+        self.get("album")
+    }
+
+    /// The `date` tag, or `year` if there is none.
+    pub fn date(&self) -> Option<&str> {
+        self.get("date").or_else(|| self.get("year"))
+    }
+
+    /// The number of the track in its album, from `track` or `tracknumber`, e.g. `3` for
+    /// `"3/12"`.
+    pub fn track_number(&self) -> Option<u32> {
+        let track = self.get("track").or_else(|| self.get("tracknumber"))?;
+        track.split('/').next()?.trim().parse().ok()
+    }
+
+    /// The title of the current song of an ICY (Shoutcast) stream.
+    pub fn icy_title(&self) -> Option<&str> {
+        self.get("icy-title")
+    }
+}
+
+impl_node_data!(Metadata, Metadata::from_node);
+
+impl Mpv {
+    /// The metadata of the current file.
+    pub fn metadata(&self) -> Result<Metadata> {
+        self.get(&METADATA)
+    }
+
+    /// The metadata of the current file, filtered by the `display-tags` option.
+    pub fn filtered_metadata(&self) -> Result<Metadata> {
+        self.get(&FILTERED_METADATA)
+    }
+
+    /// The metadata of the current chapter.
+    pub fn chapter_metadata(&self) -> Result<Metadata> {
+        self.get(&CHAPTER_METADATA)
+    }
+
+    /// The metadata exported by the video filter with `label`, e.g. `@label:lavfi=...`.
+    pub fn vf_metadata(&self, label: &str) -> Result<Metadata> {
+        self.get_property(&format!("vf-metadata/{}", label))
+    }
+}
@@ -0,0 +1,256 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use crate::events::Event;
+use crate::properties::{PLAYLIST, PLAYLIST_COUNT};
+use crate::*;
+
+use std::collections::HashMap;
+
+/// An entry of the playlist, read from `playlist`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlaylistEntry {
+    /// The id of the entry, which is unique and stays the same if the playlist is edited.
+    /// Entries parsed from a playlist file are not in the playlist yet, and have id `0`.
+    pub id: i64,
+    pub filename: String,
+    pub title: Option<String>,
+    /// Whether this is the entry that is (or is about to be) played.
+    pub current: bool,
+    /// Whether this entry is currently being played.
+    pub playing: bool,
+}
+
+impl PlaylistEntry {
+    /// Parse an entry of `playlist`.
+    pub fn from_node(node: &OwnedNode) -> Result<PlaylistEntry> {
+        let format_err = || Error::Raw(mpv_error::PropertyFormat);
+        let flag_of = |key| node.get(key).and_then(OwnedNode::to_bool).unwrap_or(false);
+
+        Ok(PlaylistEntry {
+            id: node
+                .get("id")
+                .and_then(OwnedNode::to_i64)
+                .ok_or_else(format_err)?,
+            filename: node
+                .get("filename")
+                .and_then(OwnedNode::to_str)
+                .ok_or_else(format_err)?
+                .to_owned(),
+            title: node
+                .get("title")
+                .and_then(OwnedNode::to_str)
+                .map(str::to_owned),
+            current: flag_of("current"),
+            playing: flag_of("playing"),
+        })
+    }
+}
+
+impl Mpv {
+    /// The entries of the playlist.
+    pub fn playlist(&self) -> Result<Vec<PlaylistEntry>> {
+        self.get(&PLAYLIST)?
+            .to_array()
+            .ok_or(Error::Raw(mpv_error::PropertyFormat))?
+            .iter()
+            .map(PlaylistEntry::from_node)
+            .collect()
+    }
+
+    /// The position of the entry with `id` in the playlist, or `None` if there is none.
+    pub fn playlist_position(&self, id: i64) -> Result<Option<usize>> {
+        Ok(self.playlist()?.iter().position(|entry| entry.id == id))
+    }
+
+    fn playlist_position_of(&self, id: i64) -> Result<usize> {
+        self.playlist_position(id)?
+            .ok_or(Error::Raw(mpv_error::InvalidParameter))
+    }
+
+    /// Check that the entry at `position` has `id`, after a command by index.
+    fn playlist_check_id(&self, position: usize, id: i64) -> Result<()> {
+        match self.get_property::<i64>(&format!("playlist/{}/id", position)) {
+            Ok(found) if found == id => Ok(()),
+            _ => Err(Error::Raw(mpv_error::Generic)),
+        }
+    }
+
+    /// Play the entry at `position` in the playlist.
+    pub fn playlist_play_index(&self, position: usize) -> Result<()> {
+        self.command("playlist-play-index", &[&position.to_string()])
+    }
+
+    /// Play the entry with `id`.
+    ///
+    /// mpv has no playlist commands that take an entry id, so like all operations by id, this
+    /// resolves the position of the entry and then runs the command by index. If the playlist is
+    /// edited by another client or thread in between, the command affects whatever entry is at
+    /// that position by then. This is only detected afterwards, by checking the entry at the
+    /// position, and returned as `Error::Raw(mpv_error::Generic)`, but it is neither prevented
+    /// nor undone. So edits of the playlist that may run at the same time as operations by id
+    /// have to be synchronized by the caller.
+    ///
+    /// Returns `Error::Raw(mpv_error::InvalidParameter)` if there is no entry with `id`.
+    pub fn playlist_play_id(&self, id: i64) -> Result<()> {
+        let position = self.playlist_position_of(id)?;
+        self.playlist_play_index(position)?;
+        self.playlist_check_id(position, id)
+    }
+
+    /// Remove the entry with `id` from the playlist, see `Mpv::playlist_play_id` for concurrent
+    /// edits.
+    pub fn playlist_remove_id(&self, id: i64) -> Result<()> {
+        self.playlist_remove_index(self.playlist_position_of(id)?)?;
+        match self.playlist_position(id)? {
+            Some(_) => Err(Error::Raw(mpv_error::Generic)),
+            None => Ok(()),
+        }
+    }
+
+    /// Move the entry with `id` before the entry with `before`, or to the end of the playlist if
+    /// `before` is `None`. See `Mpv::playlist_play_id` for concurrent edits.
+    pub fn playlist_move_id(&self, id: i64, before: Option<i64>) -> Result<()> {
+        let from = self.playlist_position_of(id)?;
+        let to = match before {
+            Some(before) => self.playlist_position_of(before)?,
+            None => self.get(&PLAYLIST_COUNT)? as usize,
+        };
+        self.command("playlist-move", &[&from.to_string(), &to.to_string()])?;
+        // The entry is removed before it is inserted
+        self.playlist_check_id(if from < to { to - 1 } else { to }, id)
+    }
+
+    /// Insert the file at `path` into the playlist at `position`, with optional per-file
+    /// `options` like in `Mpv::playlist_load_files`. Returns the id of the new entry.
+    ///
+    /// The entry is inserted in one step with the `insert-at` flag of `loadfile`, which
+    /// requires mpv 0.38 or later.
+    pub fn playlist_insert_at(
+        &self,
+        path: &str,
+        position: usize,
+        options: Option<&str>,
+    ) -> Result<i64> {
+        let options = options.map(|options| OwnedNode::String(options.to_owned()));
+        self.loadfile(path, "insert-at", Some(position), options)
+    }
+}
+
+/// An event of a tracked playlist entry, returned by `PlaylistTracker::handle_event`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TrackedEvent<K> {
+    /// The entry started loading, see `Event::StartFile`.
+    Started { key: K, playlist_entry_id: i64 },
+    /// The entry stopped playing, see `Event::EndFile`.
+    Ended {
+        key: K,
+        playlist_entry_id: i64,
+        reason: EndFileReason,
+        error: Option<MpvError>,
+    },
+    /// The entry was replaced by `inserted` entries, e.g. because it was a playlist file. They
+    /// are tracked with the same key, and the replaced entry is no longer tracked.
+    Redirected {
+        key: K,
+        playlist_entry_id: i64,
+        inserted: Vec<i64>,
+    },
+}
+
+/// Correlates playlist entries with keys of the caller, e.g. ids of their media items.
+///
+/// Entries are tracked with the ids returned by `Mpv::playlist_load_files`, and
+/// `handle_event` maps `StartFile` and `EndFile` events of tracked entries to their key.
+#[derive(Clone, Debug)]
+pub struct PlaylistTracker<K> {
+    keys: HashMap<i64, K>,
+}
+
+impl<K> Default for PlaylistTracker<K> {
+    fn default() -> Self {
+        PlaylistTracker {
+            keys: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Clone> PlaylistTracker<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track the entry with `playlist_entry_id` as `key`, returning the previous key if any.
+    pub fn track(&mut self, playlist_entry_id: i64, key: K) -> Option<K> {
+        self.keys.insert(playlist_entry_id, key)
+    }
+
+    /// Stop tracking the entry with `playlist_entry_id`, e.g. after removing it.
+    pub fn untrack(&mut self, playlist_entry_id: i64) -> Option<K> {
+        self.keys.remove(&playlist_entry_id)
+    }
+
+    /// The key of the entry with `playlist_entry_id`.
+    pub fn key(&self, playlist_entry_id: i64) -> Option<&K> {
+        self.keys.get(&playlist_entry_id)
+    }
+
+    /// Map `event` to the key of its entry, if it is a `StartFile` or `EndFile` event of a
+    /// tracked entry.
+    pub fn handle_event(&mut self, event: &Event) -> Option<TrackedEvent<K>> {
+        match *event {
+            Event::StartFile { playlist_entry_id } => Some(TrackedEvent::Started {
+                key: self.keys.get(&playlist_entry_id)?.clone(),
+                playlist_entry_id,
+            }),
+            Event::EndFile {
+                playlist_entry_id,
+                playlist_insert_id,
+                playlist_insert_num_entries,
+                ..
+            } if playlist_insert_num_entries > 0 => {
+                let key = self.keys.remove(&playlist_entry_id)?;
+                // Entries inserted by one command have consecutive ids
+                let inserted = (playlist_insert_id..)
+                    .take(playlist_insert_num_entries as usize)
+                    .collect::<Vec<_>>();
+                for &id in &inserted {
+                    self.keys.insert(id, key.clone());
+                }
+                Some(TrackedEvent::Redirected {
+                    key,
+                    playlist_entry_id,
+                    inserted,
+                })
+            }
+            Event::EndFile {
+                reason,
+                error,
+                playlist_entry_id,
+                ..
+            } => Some(TrackedEvent::Ended {
+                key: self.keys.get(&playlist_entry_id)?.clone(),
+                playlist_entry_id,
+                reason,
+                error,
+            }),
+            _ => None,
+        }
+    }
+}
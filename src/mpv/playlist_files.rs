@@ -0,0 +1,417 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use crate::*;
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::rc::Rc;
+
+/// A playlist file format, see `PlaylistFormat::parse` and `PlaylistFormat::export`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlaylistFormat {
+    /// M3U and M3U8, with titles from `#EXTINF` lines.
+    M3u,
+    Pls,
+    Xspf,
+}
+
+impl PlaylistFormat {
+    /// The format of a playlist file, by the extension of `path`.
+    pub fn from_path(path: &str) -> Option<PlaylistFormat> {
+        let (_, extension) = path.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "m3u" | "m3u8" => Some(PlaylistFormat::M3u),
+            "pls" => Some(PlaylistFormat::Pls),
+            "xspf" => Some(PlaylistFormat::Xspf),
+            _ => None,
+        }
+    }
+
+    /// Parse the entries of a playlist file. The entries have id `0`, and are neither current
+    /// nor playing.
+    ///
+    /// Returns `Error::Raw(mpv_error::InvalidParameter)` if `contents` is a PLS or XSPF
+    /// document without its header.
+    pub fn parse(self, contents: &str) -> Result<Vec<PlaylistEntry>> {
+        let contents = contents.trim_start_matches('\u{feff}');
+        match self {
+            PlaylistFormat::M3u => Ok(parse_m3u(contents)),
+            PlaylistFormat::Pls => parse_pls(contents),
+            PlaylistFormat::Xspf => parse_xspf(contents),
+        }
+    }
+
+    /// Write `entries` as a playlist file.
+    ///
+    /// Line breaks in titles are replaced by spaces. Returns
+    /// `Error::Raw(mpv_error::InvalidParameter)` if a filename can't be written to an M3U or PLS
+    /// document, because it contains a line break, or starts with `#` in M3U.
+    pub fn export(self, entries: &[PlaylistEntry]) -> Result<String> {
+        match self {
+            PlaylistFormat::M3u => export_m3u(entries),
+            PlaylistFormat::Pls => export_pls(entries),
+            PlaylistFormat::Xspf => Ok(export_xspf(entries)),
+        }
+    }
+}
+
+fn entry(filename: String, title: Option<String>) -> PlaylistEntry {
+    PlaylistEntry {
+        id: 0,
+        filename,
+        title,
+        current: false,
+        playing: false,
+    }
+}
+
+fn parse_m3u(contents: &str) -> Vec<PlaylistEntry> {
+    let mut entries = Vec::new();
+    let mut title = None;
+    for line in contents.lines().map(str::trim) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            // The title follows the first comma, after the duration and attributes
+            title = info
+                .split_once(',')
+                .map(|(_, title)| title.trim())
+                .filter(|title| !title.is_empty())
+                .map(str::to_owned);
+        } else if !line.is_empty() && !line.starts_with('#') {
+            entries.push(entry(line.to_owned(), title.take()));
+        }
+    }
+    entries
+}
+
+/// The filename of `entry` for line based formats, which can't contain line breaks.
+fn line_filename(entry: &PlaylistEntry) -> Result<&str> {
+    if entry.filename.contains(['\n', '\r']) {
+        Err(Error::Raw(mpv_error::InvalidParameter))
+    } else {
+        Ok(&entry.filename)
+    }
+}
+
+/// The title of `entry` for line based formats, with line breaks replaced by spaces.
+fn line_title(entry: &PlaylistEntry) -> Option<String> {
+    entry
+        .title
+        .as_ref()
+        .map(|title| title.replace(['\n', '\r'], " "))
+}
+
+fn export_m3u(entries: &[PlaylistEntry]) -> Result<String> {
+    let mut doc = String::from("#EXTM3U\n");
+    for entry in entries {
+        let filename = line_filename(entry)?;
+        // Would be read as a comment
+        if filename.starts_with('#') {
+            return Err(Error::Raw(mpv_error::InvalidParameter));
+        }
+        if let Some(title) = line_title(entry) {
+            let _ = writeln!(doc, "#EXTINF:-1,{}", title);
+        }
+        let _ = writeln!(doc, "{}", filename);
+    }
+    Ok(doc)
+}
+
+fn parse_pls(contents: &str) -> Result<Vec<PlaylistEntry>> {
+    let mut lines = contents.lines().map(str::trim).filter(|l| !l.is_empty());
+    if !matches!(lines.next(), Some(header) if header.eq_ignore_ascii_case("[playlist]")) {
+        return Err(Error::Raw(mpv_error::InvalidParameter));
+    }
+
+    let mut files = BTreeMap::new();
+    let mut titles = BTreeMap::new();
+    for line in lines {
+        let (key, value) = match line.split_once('=') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim().to_owned();
+        if let Some(Ok(n)) = key.strip_prefix("file").map(str::parse::<u32>) {
+            files.insert(n, value);
+        } else if let Some(Ok(n)) = key.strip_prefix("title").map(str::parse::<u32>) {
+            titles.insert(n, value);
+        }
+    }
+
+    Ok(files
+        .into_iter()
+        .map(|(n, file)| entry(file, titles.remove(&n)))
+        .collect())
+}
+
+fn export_pls(entries: &[PlaylistEntry]) -> Result<String> {
+    let mut doc = String::from("[playlist]\n");
+    for (i, entry) in entries.iter().enumerate() {
+        let _ = writeln!(doc, "File{}={}", i + 1, line_filename(entry)?);
+        if let Some(title) = line_title(entry) {
+            let _ = writeln!(doc, "Title{}={}", i + 1, title);
+        }
+    }
+    let _ = write!(doc, "NumberOfEntries={}\nVersion=2\n", entries.len());
+    Ok(doc)
+}
+
+/// The contents of all `<tag>` elements in `xml`, not nested in each other.
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        // Skip elements that only start with `tag`, e.g. `<tracklist>` for `track`
+        if !rest.starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            continue;
+        }
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+        if rest[..end].ends_with('/') {
+            elements.push("");
+            rest = &rest[end + 1..];
+            continue;
+        }
+        rest = &rest[end + 1..];
+        match rest.find(&close) {
+            Some(len) => {
+                elements.push(&rest[..len]);
+                rest = &rest[len + close.len()..];
+            }
+            None => break,
+        }
+    }
+    elements
+}
+
+fn xml_unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let c = entity.and_then(|(entity, _)| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32),
+        });
+        match (c, entity) {
+            (Some(c), Some((_, end))) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn percent_decode(text: &str) -> Result<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match byte {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| Error::InvalidUtf8)
+}
+
+fn percent_encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
+
+/// Returns whether `path` starts with a Windows drive, e.g. `C:/`.
+fn is_drive_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes.get(2), None | Some(b'/') | Some(b'\\'))
+}
+
+fn parse_xspf(contents: &str) -> Result<Vec<PlaylistEntry>> {
+    let playlist = xml_elements(contents, "playlist");
+    let playlist = playlist
+        .first()
+        .ok_or(Error::Raw(mpv_error::InvalidParameter))?;
+
+    let mut entries = Vec::new();
+    for track in xml_elements(playlist, "track") {
+        let location = match xml_elements(track, "location").first() {
+            Some(location) => xml_unescape(location.trim()),
+            None => continue,
+        };
+        // Local files are `file://` URIs or relative references, everything else is passed to
+        // mpv as is
+        let filename = match location.strip_prefix("file://") {
+            Some(path) => {
+                let path = percent_decode(path.strip_prefix("localhost").unwrap_or(path))?;
+                // `file:///C:/x` is the Windows path `C:/x`
+                match path.strip_prefix('/') {
+                    Some(drive_path) if is_drive_path(drive_path) => drive_path.to_owned(),
+                    _ => path,
+                }
+            }
+            None if !location.contains("://") => percent_decode(&location)?,
+            None => location,
+        };
+        let title = xml_elements(track, "title")
+            .first()
+            .map(|title| xml_unescape(title.trim()))
+            .filter(|title| !title.is_empty());
+        entries.push(entry(filename, title));
+    }
+    Ok(entries)
+}
+
+fn export_xspf(entries: &[PlaylistEntry]) -> String {
+    let mut doc = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n  <trackList>\n",
+    );
+    for entry in entries {
+        let location = if entry.filename.contains("://") {
+            entry.filename.clone()
+        } else if entry.filename.starts_with('/') {
+            format!("file://{}", percent_encode_path(&entry.filename))
+        } else {
+            percent_encode_path(&entry.filename)
+        };
+        let _ = writeln!(
+            doc,
+            "    <track>\n      <location>{}</location>",
+            xml_escape(&location)
+        );
+        if let Some(ref title) = entry.title {
+            let _ = writeln!(doc, "      <title>{}</title>", xml_escape(title));
+        }
+        doc.push_str("    </track>\n");
+    }
+    doc.push_str("  </trackList>\n</playlist>\n");
+    doc
+}
+
+impl Mpv {
+    /// Load `entries` into the playlist, each with `loadfile` and its title as the per-file
+    /// `force-media-title` option. `state` applies to the first entry and the others are
+    /// appended, so e.g. `FileState::Replace` replaces the playlist with all entries. Returns the
+    /// ids of the new entries, or `Error::Loadfiles` with the index of the entry that failed.
+    ///
+    /// mpv only uses `force-media-title` as the `media-title` while the entry plays, and not as
+    /// its title in the `playlist` property. So the titles are also kept by this crate for all
+    /// clients of the core, to be exported by `Mpv::playlist_export`.
+    pub fn playlist_load_entries(
+        &self,
+        entries: &[PlaylistEntry],
+        state: FileState,
+    ) -> Result<Vec<i64>> {
+        let mut ids = Vec::with_capacity(entries.len());
+        for (i, entry) in entries.iter().enumerate() {
+            let state = if i == 0 { state } else { FileState::Append };
+            let options = entry.title.as_ref().map(|title| {
+                OwnedNode::Map(vec![(
+                    "force-media-title".to_owned(),
+                    OwnedNode::String(title.clone()),
+                )])
+            });
+            let id = self
+                .loadfile(&entry.filename, state.val(), None, options)
+                .map_err(|error| Error::Loadfiles {
+                    index: i,
+                    error: Rc::new(error),
+                })?;
+            if let Some(ref title) = entry.title {
+                let mut titles = self.client.state.titles.lock().unwrap();
+                titles.insert(id, title.clone());
+            }
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    /// Write the current playlist as a playlist file, see `PlaylistFormat::export`. Titles are
+    /// taken from the `playlist` property, so they are known for entries that were loaded from
+    /// a playlist file, and from `Mpv::playlist_load_entries` for the entries it loaded.
+    pub fn playlist_export(&self, format: PlaylistFormat) -> Result<String> {
+        let mut playlist = self.playlist()?;
+        let titles = self.client.state.titles.lock().unwrap();
+        for entry in playlist.iter_mut().filter(|entry| entry.title.is_none()) {
+            entry.title = titles.get(&entry.id).cloned();
+        }
+        format.export(&playlist)
+    }
+}
@@ -0,0 +1,266 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use crate::events::{Event, EventContext, ObserveData, ObservedValue, ObserverGroup, PropertyData};
+use crate::*;
+
+use std::borrow::Cow;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// The name of a property, together with the type its value is read and written as.
+///
+/// Applications can declare their own keys, e.g.
+/// `const SUB_SCALE: Property<f64> = Property::new("sub-scale");`
+pub struct Property<T> {
+    name: Cow<'static, str>,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T> Property<T> {
+    pub const fn new(name: &'static str) -> Property<T> {
+        Property {
+            name: Cow::Borrowed(name),
+            _value: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<T> From<String> for Property<T> {
+    fn from(name: String) -> Property<T> {
+        Property {
+            name: Cow::Owned(name),
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for Property<T> {
+    fn clone(&self) -> Property<T> {
+        Property {
+            name: self.name.clone(),
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Property<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Property").field(&self.name).finish()
+    }
+}
+
+impl<T> PartialEq for Property<T> {
+    fn eq(&self, other: &Property<T>) -> bool {
+        self.name == other.name
+    }
+}
+
+impl<T> Eq for Property<T> {}
+
+/// The type of a field of a struct that derives `MpvProperties`. Fields of type `Option<T>` are
+/// set to `None` if the property becomes unavailable, fields of type `T` keep their value.
+pub trait PropertyField {
+    /// The type the property is observed as.
+    type Data: ObserveData;
+
+    /// Update the field with the new value of the property.
+    fn update(&mut self, value: ObservedValue<Self::Data>);
+
+    /// Update the field with `change`. Returns `false` if `change` has a different format than
+    /// `Self::Data`, e.g. because the property is also observed elsewhere in another format.
+    fn apply(&mut self, change: &PropertyData) -> Result<bool> {
+        match Self::Data::from_property_data(change) {
+            Ok(value) => {
+                self.update(value);
+                Ok(true)
+            }
+            Err(Error::Raw(mpv_error::PropertyFormat)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<T: ObserveData> PropertyField for T {
+    type Data = T;
+
+    fn update(&mut self, value: ObservedValue<T>) {
+        if let ObservedValue::Value(value) = value {
+            *self = value;
+        }
+    }
+}
+
+impl<T: ObserveData> PropertyField for Option<T> {
+    type Data = T;
+
+    fn update(&mut self, value: ObservedValue<T>) {
+        *self = value.value();
+    }
+}
+
+/// A struct whose fields mirror properties, usually implemented with
+/// `#[derive(MpvProperties)]` of the `derive` feature.
+///
+/// Each field is bound to the property named by `#[mpv(name = "...")]`, or to its name with
+/// `_` replaced by `-`. Fields marked with `#[mpv(skip)]` are ignored. The types of the fields
+/// have to implement `PropertyField`.
+///
+/// ```ignore
+/// #[derive(Default, MpvProperties)]
+/// struct PlayerState {
+///     pause: bool,
+///     #[mpv(name = "time-pos")]
+///     position: Option<f64>,
+/// }
+///
+/// let observers = PlayerState::observe_all(&ev_ctx)?;
+/// while let Some(event) = ev_ctx.wait_event(-1.) {
+///     state.apply_change(&observers, &event?)?;
+/// }
+/// ```
+pub trait MpvProperties {
+    /// The names of the mirrored properties, and the formats they are observed in.
+    fn properties() -> Vec<(&'static str, Format)>;
+
+    /// Update the field of the property that changed, if `event` is a `PropertyChange` of a
+    /// mirrored property observed by `group`, which was returned by `observe_all`. Changes of
+    /// other observations of the same properties are ignored. Returns whether a field was
+    /// updated.
+    ///
+    /// The group is passed in instead of being stored in the struct, so that the struct only
+    /// holds the property values, can be created with `Default` before observing, and can be
+    /// cloned or compared like any other value.
+    fn apply_change(&mut self, group: &ObserverGroup, event: &Event) -> Result<bool>;
+
+    /// Observe all mirrored properties, until the returned `ObserverGroup` is dropped.
+    fn observe_all(ctx: &EventContext) -> Result<ObserverGroup> {
+        ctx.observe_group(&Self::properties())
+    }
+}
+
+impl Mpv {
+    /// Get the value of `property`.
+    pub fn get<T: GetData>(&self, property: &Property<T>) -> Result<T> {
+        self.get_property(property.name())
+    }
+
+    /// Set the value of `property`.
+    pub fn set<T: SetData>(&self, property: &Property<T>, value: T) -> Result<()> {
+        self.set_property(property.name(), value)
+    }
+}
+
+// --- Playback ---
+
+pub const PAUSE: Property<bool> = Property::new("pause");
+/// Whether the core is paused for any reason, e.g. by buffering.
+pub const CORE_IDLE: Property<bool> = Property::new("core-idle");
+/// Whether no file is loaded, and the player is waiting in idle mode.
+pub const IDLE_ACTIVE: Property<bool> = Property::new("idle-active");
+pub const EOF_REACHED: Property<bool> = Property::new("eof-reached");
+pub const SEEKING: Property<bool> = Property::new("seeking");
+pub const PAUSED_FOR_CACHE: Property<bool> = Property::new("paused-for-cache");
+pub const SPEED: Property<f64> = Property::new("speed");
+/// Position in the current file in seconds.
+pub const TIME_POS: Property<f64> = Property::new("time-pos");
+pub const TIME_REMAINING: Property<f64> = Property::new("time-remaining");
+/// Position in the current file in seconds, clamped to the duration.
+pub const PLAYBACK_TIME: Property<f64> = Property::new("playback-time");
+/// Position in the current file in `0..=100`.
+pub const PERCENT_POS: Property<f64> = Property::new("percent-pos");
+/// Duration of the current file in seconds.
+pub const DURATION: Property<f64> = Property::new("duration");
+/// Timestamp of the start of the current file in seconds, usually 0.
+pub const START_TIME: Property<f64> = Property::new("start-time");
+/// Start of the A-B loop in seconds, or `"no"`.
+pub const AB_LOOP_A: Property<String> = Property::new("ab-loop-a");
+/// End of the A-B loop in seconds, or `"no"`.
+pub const AB_LOOP_B: Property<String> = Property::new("ab-loop-b");
+pub const AB_LOOP_COUNT: Property<String> = Property::new("ab-loop-count");
+pub const LOOP_FILE: Property<String> = Property::new("loop-file");
+pub const LOOP_PLAYLIST: Property<String> = Property::new("loop-playlist");
+
+// --- File ---
+
+pub const PATH: Property<String> = Property::new("path");
+pub const FILENAME: Property<String> = Property::new("filename");
+/// The title from the metadata, or the file name if there is none.
+pub const MEDIA_TITLE: Property<String> = Property::new("media-title");
+pub const FILE_FORMAT: Property<String> = Property::new("file-format");
+/// Size of the current file in bytes.
+pub const FILE_SIZE: Property<i64> = Property::new("file-size");
+pub const DEMUXER_CACHE_DURATION: Property<f64> = Property::new("demuxer-cache-duration");
+pub const DEMUXER_CACHE_STATE: Property<OwnedNode> = Property::new("demuxer-cache-state");
+pub const METADATA: Property<Metadata> = Property::new("metadata");
+/// The metadata of the current file, filtered by the `display-tags` option.
+pub const FILTERED_METADATA: Property<Metadata> = Property::new("filtered-metadata");
+pub const CHAPTER_METADATA: Property<Metadata> = Property::new("chapter-metadata");
+
+// --- Playlist, tracks and chapters ---
+
+pub const PLAYLIST: Property<OwnedNode> = Property::new("playlist");
+/// Index of the current entry in the playlist, `-1` if there is none.
+pub const PLAYLIST_POS: Property<i64> = Property::new("playlist-pos");
+pub const PLAYLIST_COUNT: Property<i64> = Property::new("playlist-count");
+pub const TRACK_LIST: Property<OwnedNode> = Property::new("track-list");
+pub const CHAPTER_LIST: Property<OwnedNode> = Property::new("chapter-list");
+/// Index of the current chapter, `-1` before the first chapter.
+pub const CHAPTER: Property<i64> = Property::new("chapter");
+pub const CHAPTERS: Property<i64> = Property::new("chapters");
+/// The edition to play, see `Mpv::set_edition`.
+pub const EDITION: Property<i64> = Property::new("edition");
+pub const CURRENT_EDITION: Property<i64> = Property::new("current-edition");
+pub const EDITIONS: Property<i64> = Property::new("editions");
+pub const EDITION_LIST: Property<OwnedNode> = Property::new("edition-list");
+
+// --- Audio ---
+
+/// Volume in percent, `100` is the original volume.
+pub const VOLUME: Property<f64> = Property::new("volume");
+pub const MUTE: Property<bool> = Property::new("mute");
+/// Audio delay in seconds.
+pub const AUDIO_DELAY: Property<f64> = Property::new("audio-delay");
+pub const AUDIO_PARAMS: Property<AudioParams> = Property::new("audio-params");
+pub const AUDIO_OUT_PARAMS: Property<AudioParams> = Property::new("audio-out-params");
+/// The name of the selected audio device, see `AudioDevice::name`.
+pub const AUDIO_DEVICE: Property<String> = Property::new("audio-device");
+pub const AUDIO_DEVICE_LIST: Property<Vec<AudioDevice>> = Property::new("audio-device-list");
+
+// --- Video and subtitles ---
+
+pub const FULLSCREEN: Property<bool> = Property::new("fullscreen");
+/// Width of the video in pixels, before filters.
+pub const WIDTH: Property<i64> = Property::new("width");
+/// Height of the video in pixels, before filters.
+pub const HEIGHT: Property<i64> = Property::new("height");
+pub const CONTAINER_FPS: Property<f64> = Property::new("container-fps");
+pub const ESTIMATED_VF_FPS: Property<f64> = Property::new("estimated-vf-fps");
+pub const HWDEC_CURRENT: Property<String> = Property::new("hwdec-current");
+pub const VIDEO_PARAMS: Property<VideoParams> = Property::new("video-params");
+pub const VIDEO_OUT_PARAMS: Property<VideoParams> = Property::new("video-out-params");
+/// Subtitle delay in seconds.
+pub const SUB_DELAY: Property<f64> = Property::new("sub-delay");
+
+// --- Player ---
+
+pub const MPV_VERSION: Property<String> = Property::new("mpv-version");
@@ -39,7 +39,7 @@ impl Mpv {
         T: RefUnwindSafe,
         U: RefUnwindSafe,
     {
-        match self.protocols_guard.compare_exchange(
+        match self.client.protocols_guard.compare_exchange(
             false,
             true,
             Ordering::AcqRel,
@@ -0,0 +1,217 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use super::mpv_err;
+use crate::events::{mpv_event_id, Deadline, Event, EventContext, OwnedEvent};
+use crate::properties::{CONTAINER_FPS, START_TIME};
+use crate::*;
+
+use std::sync::atomic::Ordering;
+
+/// Where a `Seek` goes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SeekTarget {
+    /// A position in seconds.
+    Absolute(f64),
+    /// An offset from the current position in seconds, may be negative.
+    Relative(f64),
+    /// A position in percent of the duration.
+    AbsolutePercent(f64),
+    /// An offset from the current position in percent of the duration, may be negative.
+    RelativePercent(f64),
+    /// The start of the chapter with this index in `Mpv::chapters`.
+    Chapter(usize),
+    /// The frame with this index, computed from `container-fps` and `start-time`. Always seeks
+    /// exactly. Fails with `Error::Raw(mpv_error::InvalidParameter)` if the file has no usable
+    /// frame rate, e.g. because it has no video.
+    Frame(i64),
+}
+
+/// How precisely a `Seek` goes to its target, see the `hr-seek` option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SeekPrecision {
+    /// Use the default of the `hr-seek` option.
+    Default,
+    /// Seek to the exact position, by decoding from the previous keyframe.
+    Exact,
+    /// Seek to the nearest keyframe, which is fast but imprecise.
+    Keyframes,
+}
+
+/// A seek, run with `Mpv::seek` or `EventContext::seek_and_wait`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Seek {
+    target: SeekTarget,
+    precision: SeekPrecision,
+}
+
+impl Seek {
+    /// Seek to `target` with `SeekPrecision::Default`.
+    pub fn new(target: SeekTarget) -> Seek {
+        Seek {
+            target,
+            precision: SeekPrecision::Default,
+        }
+    }
+
+    pub fn absolute(secs: f64) -> Seek {
+        Seek::new(SeekTarget::Absolute(secs))
+    }
+
+    pub fn relative(secs: f64) -> Seek {
+        Seek::new(SeekTarget::Relative(secs))
+    }
+
+    pub fn absolute_percent(percent: f64) -> Seek {
+        Seek::new(SeekTarget::AbsolutePercent(percent))
+    }
+
+    pub fn relative_percent(percent: f64) -> Seek {
+        Seek::new(SeekTarget::RelativePercent(percent))
+    }
+
+    pub fn chapter(index: usize) -> Seek {
+        Seek::new(SeekTarget::Chapter(index))
+    }
+
+    pub fn frame(index: i64) -> Seek {
+        Seek::new(SeekTarget::Frame(index))
+    }
+
+    pub fn precision(mut self, precision: SeekPrecision) -> Seek {
+        self.precision = precision;
+        self
+    }
+
+    /// Seek with `SeekPrecision::Exact`.
+    pub fn exact(self) -> Seek {
+        self.precision(SeekPrecision::Exact)
+    }
+
+    /// Seek with `SeekPrecision::Keyframes`.
+    pub fn keyframes(self) -> Seek {
+        self.precision(SeekPrecision::Keyframes)
+    }
+
+    /// The arguments of the `seek` command.
+    fn args(&self, mpv: &Mpv) -> Result<(String, String)> {
+        let mut precision = self.precision;
+        let (target, mode) = match self.target {
+            SeekTarget::Absolute(secs) => (secs, "absolute"),
+            SeekTarget::Relative(secs) => (secs, "relative"),
+            SeekTarget::AbsolutePercent(percent) => (percent, "absolute-percent"),
+            SeekTarget::RelativePercent(percent) => (percent, "relative-percent"),
+            SeekTarget::Chapter(index) => {
+                let chapter = mpv
+                    .chapters()?
+                    .into_iter()
+                    .nth(index)
+                    .ok_or(Error::Raw(mpv_error::InvalidParameter))?;
+                (chapter.time, "absolute")
+            }
+            SeekTarget::Frame(index) => {
+                precision = SeekPrecision::Exact;
+                let fps = mpv
+                    .get(&CONTAINER_FPS)
+                    .ok()
+                    .filter(|fps| fps.is_finite() && *fps > 0.)
+                    .ok_or(Error::Raw(mpv_error::InvalidParameter))?;
+                let start = mpv.get(&START_TIME).unwrap_or(0.);
+                (start + index as f64 / fps, "absolute")
+            }
+        };
+        let flags = match precision {
+            SeekPrecision::Default => mode.to_owned(),
+            SeekPrecision::Exact => format!("{}+exact", mode),
+            SeekPrecision::Keyframes => format!("{}+keyframes", mode),
+        };
+        Ok((target.to_string(), flags))
+    }
+}
+
+impl Mpv {
+    /// Run `seek`. The seek is asynchronous, see `EventContext::seek_and_wait` to wait for it.
+    pub fn seek(&self, seek: &Seek) -> Result<()> {
+        let (target, flags) = seek.args(self)?;
+        self.command("seek", &[&target, &flags])
+    }
+}
+
+/// The outcome of `EventContext::seek_and_wait`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeekWait {
+    /// `Ok(true)` if playback restarted at the new position, `Ok(false)` if it did not within
+    /// the timeout, or if the file ended instead, e.g. because the target was past its end.
+    /// The error of the `seek` command if it failed.
+    pub result: Result<bool>,
+    /// All other events received while waiting, in order, e.g. changes of observed properties.
+    pub skipped: Vec<Result<OwnedEvent>>,
+}
+
+impl EventContext {
+    /// Run `seek` asynchronously, and wait for `timeout` seconds until playback restarts at the
+    /// new position. A negative `timeout` waits indefinitely.
+    ///
+    /// The restart is the first `PlaybackRestart` after the reply of the `seek` command, so
+    /// restarts of earlier seeks or of the file start are not mistaken for it. The reply uses an
+    /// id allocated like [observe](#method.observe). Returns `Err` if the seek could not be
+    /// sent, before any event was received.
+    pub fn seek_and_wait(&mut self, seek: &Seek, timeout: f64) -> Result<SeekWait> {
+        let client = self.client();
+        let id = client.next_observer_id.fetch_add(1, Ordering::Relaxed);
+        let mpv = Mpv {
+            ctx: client.ctx,
+            client,
+        };
+        let (target, flags) = seek.args(&mpv)?;
+        mpv.command_async(id, "seek", &[&target, &flags])?;
+
+        let deadline = Deadline::new(timeout);
+        let mut replied = false;
+        let mut skipped = Vec::new();
+        let result = loop {
+            let event = self.wait_raw_event(deadline.remaining());
+            if event.event_id == mpv_event_id::CommandReply && event.reply_userdata == id {
+                if let Err(e) = mpv_err((), event.error) {
+                    break Err(e);
+                }
+                replied = true;
+            } else if replied && event.event_id == mpv_event_id::PlaybackRestart {
+                break Ok(true);
+            } else if let Some(converted) = self.convert_event(event) {
+                // A file that ends before the reply is not the one the seek applies to
+                let ended = match converted {
+                    Ok(Event::Shutdown) => true,
+                    Ok(Event::EndFile { .. }) => replied,
+                    _ => false,
+                };
+                skipped.push(converted.and_then(|event| event.to_owned()));
+                if ended {
+                    break Ok(false);
+                }
+            }
+            if deadline.expired() {
+                break Ok(false);
+            }
+        };
+        Ok(SeekWait { result, skipped })
+    }
+}
@@ -0,0 +1,250 @@
+// Copyright (C) 2016  ParadoxSpiral
+//
+// This file is part of mpv-rs.
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
+
+use crate::properties::TRACK_LIST;
+use crate::*;
+
+/// The kind of a track.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrackKind {
+    Video,
+    Audio,
+    Sub,
+}
+
+impl TrackKind {
+    /// The name of the kind, as used by `track-list/N/type`.
+    pub fn name(self) -> &'static str {
+        match self {
+            TrackKind::Video => "video",
+            TrackKind::Audio => "audio",
+            TrackKind::Sub => "sub",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TrackKind> {
+        match name {
+            "video" => Some(TrackKind::Video),
+            "audio" => Some(TrackKind::Audio),
+            "sub" => Some(TrackKind::Sub),
+            _ => None,
+        }
+    }
+
+    /// The property that selects the track of this kind, e.g. `"aid"`.
+    pub fn property(self) -> &'static str {
+        match self {
+            TrackKind::Video => "vid",
+            TrackKind::Audio => "aid",
+            TrackKind::Sub => "sid",
+        }
+    }
+
+    fn add_command(self) -> &'static str {
+        match self {
+            TrackKind::Video => "video-add",
+            TrackKind::Audio => "audio-add",
+            TrackKind::Sub => "sub-add",
+        }
+    }
+}
+
+/// Parameters of a track as reported by the demuxer. They may be inaccurate, and are only
+/// present if known.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DemuxParams {
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub fps: Option<f64>,
+    /// Pixel aspect ratio.
+    pub par: Option<f64>,
+    /// Rotation in degrees.
+    pub rotation: Option<i64>,
+    pub channel_count: Option<i64>,
+    /// Channel layout, e.g. `"stereo"`.
+    pub channels: Option<String>,
+    pub samplerate: Option<i64>,
+    /// Bitrate in bits per second.
+    pub bitrate: Option<i64>,
+}
+
+/// A track of the current file, read from `track-list`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Track {
+    /// The id used to select the track, unique per kind.
+    pub id: i64,
+    pub kind: TrackKind,
+    /// The id of the track in the file, if known.
+    pub src_id: Option<i64>,
+    pub title: Option<String>,
+    pub lang: Option<String>,
+    pub codec: Option<String>,
+    /// Whether the track is an image, e.g. cover art of an audio file.
+    pub albumart: bool,
+    pub default: bool,
+    pub forced: bool,
+    /// Whether the track was loaded from an external file, e.g. with `Mpv::subtitle_add_select`.
+    pub external: bool,
+    pub external_filename: Option<String>,
+    pub selected: bool,
+    pub demux: DemuxParams,
+}
+
+impl Track {
+    /// Parse an entry of `track-list`.
+    pub fn from_node(node: &OwnedNode) -> Result<Track> {
+        let format_err = || Error::Raw(mpv_error::PropertyFormat);
+        let i64_of = |key| node.get(key).and_then(OwnedNode::to_i64);
+        let f64_of = |key| node.get(key).and_then(OwnedNode::to_f64);
+        let str_of = |key| node.get(key).and_then(OwnedNode::to_str).map(str::to_owned);
+        let flag_of = |key| node.get(key).and_then(OwnedNode::to_bool).unwrap_or(false);
+
+        Ok(Track {
+            id: i64_of("id").ok_or_else(format_err)?,
+            kind: node
+                .get("type")
+                .and_then(OwnedNode::to_str)
+                .and_then(TrackKind::from_name)
+                .ok_or_else(format_err)?,
+            src_id: i64_of("src-id"),
+            title: str_of("title"),
+            lang: str_of("lang"),
+            codec: str_of("codec"),
+            albumart: flag_of("albumart"),
+            default: flag_of("default"),
+            forced: flag_of("forced"),
+            external: flag_of("external"),
+            external_filename: str_of("external-filename"),
+            selected: flag_of("selected"),
+            demux: DemuxParams {
+                width: i64_of("demux-w"),
+                height: i64_of("demux-h"),
+                fps: f64_of("demux-fps"),
+                par: f64_of("demux-par"),
+                rotation: i64_of("demux-rotation"),
+                channel_count: i64_of("demux-channel-count"),
+                channels: str_of("demux-channels"),
+                samplerate: i64_of("demux-samplerate"),
+                bitrate: i64_of("demux-bitrate"),
+            },
+        })
+    }
+}
+
+impl Mpv {
+    /// The tracks of the current file, from `track-list`.
+    pub fn tracks(&self) -> Result<Vec<Track>> {
+        self.get(&TRACK_LIST)?
+            .to_array()
+            .ok_or(Error::Raw(mpv_error::PropertyFormat))?
+            .iter()
+            .map(Track::from_node)
+            .collect()
+    }
+
+    /// Select the track `id` of `kind`, see `Track::id`.
+    pub fn select_track(&self, kind: TrackKind, id: i64) -> Result<()> {
+        self.set_property(kind.property(), id)
+    }
+
+    /// Select no track of `kind`, e.g. to disable subtitles.
+    pub fn deselect_track(&self, kind: TrackKind) -> Result<()> {
+        self.set_property(kind.property(), "no")
+    }
+
+    pub(crate) fn track_add(
+        &self,
+        kind: TrackKind,
+        path: &str,
+        flag: &str,
+        title: Option<&str>,
+        lang: Option<&str>,
+    ) -> Result<()> {
+        // Passed as separate arguments, so paths and titles don't have to be quoted
+        let ret = match (title, lang) {
+            (None, None) => self.command_ret(kind.add_command(), &[path, flag]),
+            (Some(t), None) => self.command_ret(kind.add_command(), &[path, flag, t]),
+            (Some(t), Some(l)) => self.command_ret(kind.add_command(), &[path, flag, t, l]),
+            (None, Some(_)) => return Err(Error::Raw(mpv_error::InvalidParameter)),
+        };
+        ret.map(|_| ())
+    }
+
+    /// Add and select the audio track immediately, see `Mpv::subtitle_add_select`.
+    ///
+    /// Fails with `Error::Raw(mpv_error::InvalidParameter)` if a language but no title is given.
+    pub fn audio_add_select(
+        &self,
+        path: &str,
+        title: Option<&str>,
+        lang: Option<&str>,
+    ) -> Result<()> {
+        self.track_add(TrackKind::Audio, path, "select", title, lang)
+    }
+
+    /// Add the audio track without selecting it, see `Mpv::subtitle_add_auto`.
+    ///
+    /// Fails with `Error::Raw(mpv_error::InvalidParameter)` if a language but no title is given.
+    pub fn audio_add_auto(
+        &self,
+        path: &str,
+        title: Option<&str>,
+        lang: Option<&str>,
+    ) -> Result<()> {
+        self.track_add(TrackKind::Audio, path, "auto", title, lang)
+    }
+
+    /// Select the audio track, or add it if it was not added yet, see
+    /// `Mpv::subtitle_add_cached`.
+    pub fn audio_add_cached(&self, path: &str) -> Result<()> {
+        self.track_add(TrackKind::Audio, path, "cached", None, None)
+    }
+
+    /// Add and select the video track immediately, see `Mpv::subtitle_add_select`.
+    ///
+    /// Fails with `Error::Raw(mpv_error::InvalidParameter)` if a language but no title is given.
+    pub fn video_add_select(
+        &self,
+        path: &str,
+        title: Option<&str>,
+        lang: Option<&str>,
+    ) -> Result<()> {
+        self.track_add(TrackKind::Video, path, "select", title, lang)
+    }
+
+    /// Add the video track without selecting it, see `Mpv::subtitle_add_auto`.
+    ///
+    /// Fails with `Error::Raw(mpv_error::InvalidParameter)` if a language but no title is given.
+    pub fn video_add_auto(
+        &self,
+        path: &str,
+        title: Option<&str>,
+        lang: Option<&str>,
+    ) -> Result<()> {
+        self.track_add(TrackKind::Video, path, "auto", title, lang)
+    }
+
+    /// Select the video track, or add it if it was not added yet, see
+    /// `Mpv::subtitle_add_cached`.
+    pub fn video_add_cached(&self, path: &str) -> Result<()> {
+        self.track_add(TrackKind::Video, path, "cached", None, None)
+    }
+}
@@ -16,12 +16,18 @@
 // License along with this library; if not, write to the Free Software
 // Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA  02110-1301  USA
 
-use crate::events::{Event, PropertyData};
+use crate::dispatcher::EventDispatcher;
+use crate::events::{
+    Event, EventContext, EventKind, EventMask, ObservedValue, OwnedEvent, OwnedPropertyData,
+    PropertyData,
+};
+use crate::properties::{self, Property};
 use crate::*;
 
 use std::collections::HashMap;
+use std::rc::Rc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[test]
 fn initializer() {
@@ -39,6 +45,237 @@ fn initializer() {
     assert_eq!(30i64, mpv.get_property("volume").unwrap());
 }
 
+#[test]
+fn apply_args() {
+    let mut files = Vec::new();
+    let mpv = Mpv::with_initializer(|init| {
+        files = init.apply_args([
+            "--vo=null",
+            "--volume=30",
+            "--no-input-default-bindings",
+            "--osc",
+            "test-data/speech_12kbps_mb.wav",
+            "--",
+            "--not-an-option",
+        ])?;
+        Ok(())
+    })
+    .unwrap();
+
+    assert_eq!(files, ["test-data/speech_12kbps_mb.wav", "--not-an-option"]);
+    assert_eq!(30i64, mpv.get_property("volume").unwrap());
+    assert!(!mpv.get_property::<bool>("input-default-bindings").unwrap());
+    assert!(mpv.get_property::<bool>("osc").unwrap());
+
+    let err = Mpv::with_initializer(|init| {
+        init.apply_args(["--volume=30", "--no-such-option=1"])?;
+        Ok(())
+    })
+    .err()
+    .unwrap();
+    assert!(matches!(err, Error::Argument { index: 1, .. }));
+}
+
+#[test]
+fn config_str() {
+    let mpv = Mpv::new().unwrap();
+    mpv.load_config_str(
+        "# A comment\n\
+         volume=30 # trailing comment\n\
+         --ytdl-format = \"best # not a comment\"\n\
+         no-osc\n\
+         [quiet]\n\
+         volume=5\n\
+         [default]\n\
+         sub-font=%5%a b c\n",
+    )
+    .unwrap();
+
+    assert_eq!(30i64, mpv.get_property("volume").unwrap());
+    let format: String = mpv.get_property("ytdl-format").unwrap();
+    assert_eq!(format, "best # not a comment");
+    assert!(!mpv.get_property::<bool>("osc").unwrap());
+    let font: String = mpv.get_property("sub-font").unwrap();
+    assert_eq!(font, "a b c");
+
+    mpv.apply_profile("quiet").unwrap();
+    assert_eq!(5i64, mpv.get_property("volume").unwrap());
+    mpv.load_config_str(
+        "[louder]\nprofile-desc=Louder\nprofile=quiet\nvolume-max=200\nvolume=150\n",
+    )
+    .unwrap();
+    mpv.load_config_str("profile=louder\n").unwrap();
+    assert_eq!(150i64, mpv.get_property("volume").unwrap());
+
+    let err = mpv
+        .load_config_str("volume=30\n\nno-such-option=1\n")
+        .unwrap_err();
+    assert!(matches!(err, Error::Config { line: 3, .. }));
+    let err = mpv.load_config_str("volume=\"30\n").unwrap_err();
+    assert!(matches!(err, Error::Config { line: 1, .. }));
+    mpv.load_config_str("[broken]\n\nno-such-option=1\n")
+        .unwrap();
+    let err = mpv.apply_profile("broken").unwrap_err();
+    assert!(matches!(err, Error::Config { line: 3, .. }));
+    let err = mpv
+        .load_config_str(
+            "[auto]
+profile-cond=true
+volume=1
+",
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        Error::Config {
+            line: 2,
+            error: Rc::new(Error::Raw(mpv_error::NotImplemented)),
+        }
+    );
+}
+
+#[test]
+fn introspection() {
+    let mpv = Mpv::new().unwrap();
+    assert!(mpv.property_list().unwrap().iter().any(|p| p == "time-pos"));
+    assert!(mpv.option_list().unwrap().iter().any(|o| o == "volume"));
+
+    let volume = mpv.option_info("volume").unwrap();
+    assert_eq!(volume.option_type, OptionType::Float);
+    assert_eq!(volume.option_type.format(), Format::Double);
+    assert_eq!(volume.min, Some(0.));
+    assert_eq!(volume.default_value, OwnedNode::Double(100.));
+    assert!(volume.check_value(&OwnedNode::Double(50.)).is_ok());
+    assert_eq!(
+        volume.check_value(&OwnedNode::Double(-1.)),
+        Err(Error::Raw(mpv_error::OptionError))
+    );
+    assert_eq!(
+        volume.check_value(&OwnedNode::String("loud".to_owned())),
+        Err(Error::Raw(mpv_error::OptionFormat))
+    );
+
+    let keep_open = mpv.option_info("keep-open").unwrap();
+    assert_eq!(keep_open.option_type, OptionType::Choice);
+    assert!(keep_open.choices.iter().any(|c| c == "always"));
+    assert!(keep_open
+        .check_value(&OwnedNode::String("always".to_owned()))
+        .is_ok());
+    assert!(keep_open
+        .check_value(&OwnedNode::String("sometimes".to_owned()))
+        .is_err());
+}
+
+#[test]
+fn property_keys() {
+    const SUB_SCALE: Property<f64> = Property::new("sub-scale");
+
+    let mpv = Mpv::new().unwrap();
+    mpv.set(&properties::PAUSE, true).unwrap();
+    assert!(mpv.get(&properties::PAUSE).unwrap());
+    mpv.set(&properties::VOLUME, 50.).unwrap();
+    assert_eq!(mpv.get(&properties::VOLUME).unwrap(), 50.);
+    mpv.set(&SUB_SCALE, 2.).unwrap();
+    assert_eq!(mpv.get(&SUB_SCALE).unwrap(), 2.);
+
+    let dynamic: Property<String> = format!("option-info/{}/name", "volume").into();
+    assert_eq!(mpv.get(&dynamic).unwrap(), "volume");
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_properties() {
+    use crate::properties::MpvProperties;
+
+    #[derive(Default, MpvProperties)]
+    struct PlayerState {
+        volume: i64,
+        #[mpv(name = "media-title")]
+        title: Option<String>,
+        #[mpv(skip)]
+        changes: usize,
+    }
+
+    let mpv = Mpv::new().unwrap();
+    let mut ev_ctx = mpv.create_event_context();
+    ev_ctx.disable_deprecated_events().unwrap();
+    let observers = PlayerState::observe_all(&ev_ctx).unwrap();
+    // Other observations of the same property are not applied
+    ev_ctx
+        .observe_property("volume", Format::String, 1)
+        .unwrap();
+
+    let mut state = PlayerState::default();
+    mpv.set_property("volume", 30).unwrap();
+    mpv.playlist_load_files(&[(
+        "test-data/speech_12kbps_mb.wav",
+        FileState::AppendPlay,
+        None,
+    )])
+    .unwrap();
+    while let Some(ev) = ev_ctx.wait_event(3.) {
+        if state.apply_change(&observers, &ev.unwrap()).unwrap() {
+            state.changes += 1;
+        }
+        if state.volume == 30 && state.title.is_some() {
+            break;
+        }
+    }
+
+    assert_eq!(state.volume, 30);
+    assert_eq!(state.title.as_deref(), Some("speech_12kbps_mb.wav"));
+    assert!(state.changes >= 2);
+}
+
+// The derived code only uses absolute paths, so it works without the prelude
+#[cfg(feature = "derive")]
+#[no_implicit_prelude]
+#[allow(dead_code)]
+mod derive_without_prelude {
+    #[derive(crate::MpvProperties)]
+    struct PlayerState {
+        pause: bool,
+        #[mpv(name = "time-pos")]
+        position: ::std::option::Option<f64>,
+    }
+}
+
+#[test]
+fn builder() {
+    let mpv = Mpv::builder()
+        .vo("null")
+        .ao("null")
+        .idle(Idle::Yes)
+        .load_scripts(false)
+        .osd_level(0)
+        .log_level(mpv_log_level::Info)
+        .build()
+        .unwrap();
+
+    assert_eq!(0i64, mpv.get_property("osd-level").unwrap());
+    assert!(!mpv.get_property::<bool>("load-scripts").unwrap());
+    let ao: String = mpv.get_property("ao").unwrap();
+    assert_eq!(ao, "null");
+
+    let mut ev_ctx = mpv.create_event_context();
+    mpv.playlist_load_files(&[(
+        "test-data/speech_12kbps_mb.wav",
+        FileState::AppendPlay,
+        None,
+    )])
+    .unwrap();
+    loop {
+        match ev_ctx.wait_event(3.) {
+            Some(Ok(Event::LogMessage { .. })) => break,
+            Some(_) => continue,
+            None => panic!("No log message received"),
+        }
+    }
+
+    let osd_level: i64 = Mpv::new().unwrap().get_property("osd-level").unwrap();
+    assert_ne!(osd_level, 0);
+}
+
 #[test]
 fn properties() {
     let mpv = Mpv::new().unwrap();
@@ -76,7 +313,7 @@ macro_rules! assert_event_occurs {
                 $( Some($expected) )|+ => {
                     break;
                 },
-                None => {
+                None | Some(Ok(Event::PropertyChange { change: PropertyData::Unavailable, .. })) => {
                     continue
                 },
                 other => panic!("Event did not occur, got: {:?}", other),
@@ -85,6 +322,29 @@ macro_rules! assert_event_occurs {
     }
 }
 
+// Play the test file without video or audio output. `setup` runs before the file is loaded, so it
+// can observe events from the start.
+fn play_speech<T>(
+    options: &[(&str, &str)],
+    setup: impl FnOnce(&Mpv, &mut EventContext) -> T,
+) -> (Mpv, EventContext, T) {
+    let mut builder = Mpv::builder().vo("null").ao("null");
+    for (name, value) in options {
+        builder = builder.option(name, value);
+    }
+    let mpv = builder.build().unwrap();
+    let mut ev_ctx = mpv.create_event_context();
+    ev_ctx.disable_deprecated_events().unwrap();
+    let value = setup(&mpv, &mut ev_ctx);
+    mpv.playlist_load_files(&[(
+        "test-data/speech_12kbps_mb.wav",
+        FileState::AppendPlay,
+        None,
+    )])
+    .unwrap();
+    (mpv, ev_ctx, value)
+}
+
 #[test]
 fn events() {
     let mpv = Mpv::new().unwrap();
@@ -127,7 +387,7 @@ fn events() {
         None,
     )])
     .unwrap();
-    assert_event_occurs!(ev_ctx, 10., Ok(Event::StartFile));
+    assert_event_occurs!(ev_ctx, 10., Ok(Event::StartFile { .. }));
     assert_event_occurs!(
         ev_ctx,
         10.,
@@ -137,8 +397,27 @@ fn events() {
             reply_userdata: 1,
         })
     );
-    assert_event_occurs!(ev_ctx, 20., Err(Error::Raw(mpv_error::UnknownFormat)));
-    assert!(ev_ctx.wait_event(3.).is_none());
+    assert_event_occurs!(
+        ev_ctx,
+        20.,
+        Ok(Event::EndFile {
+            reason: mpv_end_file_reason::Error,
+            error: Some(mpv_error::UnknownFormat),
+            ..
+        })
+    );
+    // The media title is unavailable after the file ended
+    loop {
+        match ev_ctx.wait_event(3.) {
+            Some(Ok(Event::PropertyChange {
+                name: "media-title",
+                change: PropertyData::Unavailable,
+                reply_userdata: 1,
+            })) => continue,
+            None => break,
+            other => panic!("Unexpected event: {:?}", other),
+        }
+    }
 
     mpv.playlist_load_files(&[(
         "test-data/speech_12kbps_mb.wav",
@@ -146,7 +425,7 @@ fn events() {
         None,
     )])
     .unwrap();
-    assert_event_occurs!(ev_ctx, 10., Ok(Event::StartFile));
+    assert_event_occurs!(ev_ctx, 10., Ok(Event::StartFile { .. }));
     assert_event_occurs!(
         ev_ctx,
         3.,
@@ -156,14 +435,854 @@ fn events() {
             reply_userdata: 1,
         })
     );
-    assert_event_occurs!(ev_ctx, 3., Ok(Event::AudioReconfig));
-    assert_event_occurs!(ev_ctx, 3., Ok(Event::AudioReconfig));
+    assert_event_occurs!(ev_ctx, 3., Ok(Event::AudioReconfig { .. }));
+    assert_event_occurs!(ev_ctx, 3., Ok(Event::AudioReconfig { .. }));
     assert_event_occurs!(ev_ctx, 3., Ok(Event::FileLoaded));
-    assert_event_occurs!(ev_ctx, 3., Ok(Event::AudioReconfig));
+    assert_event_occurs!(ev_ctx, 3., Ok(Event::AudioReconfig { .. }));
     assert_event_occurs!(ev_ctx, 3., Ok(Event::PlaybackRestart));
     assert!(ev_ctx.wait_event(3.).is_none());
 }
 
+#[test]
+fn event_masks() {
+    let mpv = Mpv::new().unwrap();
+    let ev_ctx = mpv.create_event_context();
+    assert_eq!(ev_ctx.enabled_events(), EventMask::mpv_default());
+    assert_eq!(EventKind::StartFile.name(), "start-file");
+    assert_eq!(EventKind::from_name("end-file"), Some(EventKind::EndFile));
+
+    ev_ctx.disable_all_events().unwrap();
+    assert_eq!(
+        ev_ctx.enabled_events(),
+        EventMask::from(EventKind::Shutdown)
+    );
+
+    ev_ctx
+        .enable_events(EventKind::StartFile | EventKind::EndFile)
+        .unwrap();
+    let enabled = ev_ctx.enabled_events();
+    assert!(enabled.contains(EventKind::EndFile));
+    assert!(!enabled.contains(EventKind::FileLoaded));
+    assert_eq!(enabled.iter().count(), 3);
+
+    // The mask belongs to the client, not the context
+    drop(ev_ctx);
+    assert_eq!(mpv.create_event_context().enabled_events(), enabled);
+}
+
+#[test]
+fn dispatcher() {
+    let mpv = Mpv::new().unwrap();
+    let ev_ctx = mpv.create_event_context();
+    // Does not collide with the id of the property handler
+    ev_ctx.observe_property("mute", Format::Flag, 0).unwrap();
+    let dispatcher = EventDispatcher::new(ev_ctx);
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let volume_tx = tx.clone();
+    let volume = dispatcher
+        .on_property(&Property::<i64>::new("volume"), move |change| {
+            if let ObservedValue::Value(v) = change {
+                volume_tx.send(v).unwrap();
+            }
+        })
+        .unwrap();
+    let loaded = dispatcher.on(EventKind::FileLoaded, move |_| tx.send(-1).unwrap());
+
+    assert_eq!(rx.recv_timeout(Duration::from_secs(3)), Ok(100));
+    mpv.set_property("volume", 0).unwrap();
+    assert_eq!(rx.recv_timeout(Duration::from_secs(3)), Ok(0));
+
+    drop(loaded);
+    mpv.playlist_load_files(&[(
+        "test-data/speech_12kbps_mb.wav",
+        FileState::AppendPlay,
+        None,
+    )])
+    .unwrap();
+    assert!(rx.recv_timeout(Duration::from_secs(1)).is_err());
+
+    let mut ev_ctx = dispatcher.into_inner();
+    // The property is unobserved even after the dispatcher is gone
+    drop(volume);
+    mpv.set_property("volume", 50).unwrap();
+    while let Some(ev) = ev_ctx.wait_event(1.) {
+        assert!(!matches!(
+            ev,
+            Ok(Event::PropertyChange { name: "volume", .. })
+        ));
+    }
+    ev_ctx.disable_all_events().unwrap();
+}
+
+#[test]
+#[should_panic(expected = "handler panicked")]
+fn dispatcher_panic() {
+    let mpv = Mpv::new().unwrap();
+    let dispatcher = EventDispatcher::new(mpv.create_event_context());
+    let _volume = dispatcher
+        .on_property(&properties::VOLUME, |_| panic!("handler panicked"))
+        .unwrap();
+    thread::sleep(Duration::from_secs(1));
+    dispatcher.into_inner();
+}
+
+#[test]
+fn hooks() {
+    let mpv = Mpv::new().unwrap();
+    let mut ev_ctx = mpv.create_event_context();
+    ev_ctx.disable_deprecated_events().unwrap();
+    ev_ctx.hook_add("on_load", 0, 7).unwrap();
+
+    mpv.playlist_load_files(&[(
+        "test-data/speech_12kbps_mb.wav",
+        FileState::AppendPlay,
+        None,
+    )])
+    .unwrap();
+    assert_event_occurs!(ev_ctx, 3., Ok(Event::StartFile { .. }));
+
+    let start = Instant::now();
+    let id = loop {
+        assert!(
+            start.elapsed() < Duration::from_secs(3),
+            "Hook did not run in time"
+        );
+        match ev_ctx.wait_event(3.) {
+            Some(Ok(Event::Hook {
+                name: "on_load",
+                id,
+                reply_userdata: 7,
+            })) => break id,
+            Some(Ok(_)) => continue,
+            other => panic!("Hook did not run, got: {:?}", other),
+        }
+    };
+    ev_ctx.hook_continue(id).unwrap();
+    assert_event_occurs!(ev_ctx, 3., Ok(Event::FileLoaded));
+}
+
+#[test]
+fn observers() {
+    let mpv = Mpv::new().unwrap();
+    let mut ev_ctx = mpv.create_event_context();
+    ev_ctx.disable_deprecated_events().unwrap();
+
+    let volume = ev_ctx.observe::<i64>("volume").unwrap();
+    let title = ev_ctx.observe_key(&properties::MEDIA_TITLE).unwrap();
+    assert_ne!(volume.id(), title.id());
+    assert_eq!(title.name(), "media-title");
+
+    let mut values = Vec::new();
+    while let Some(ev) = ev_ctx.wait_event(1.) {
+        let ev = ev.unwrap();
+        if let Some(value) = volume.change(&ev) {
+            values.push(value.unwrap());
+        }
+        if let Some(value) = title.change(&ev) {
+            assert_eq!(value.unwrap(), ObservedValue::Unavailable);
+        }
+    }
+    assert_eq!(values, vec![ObservedValue::Value(100)]);
+
+    drop(volume);
+    mpv.set_property("volume", 0).unwrap();
+    while let Some(ev) = ev_ctx.wait_event(1.) {
+        assert!(!matches!(
+            ev,
+            Ok(Event::PropertyChange { name: "volume", .. })
+        ));
+    }
+}
+
+#[test]
+fn clients() {
+    let mpv = Mpv::new().unwrap();
+    let client = mpv.create_client("second").unwrap();
+    let weak = mpv.create_weak_client("weak").unwrap();
+    assert_eq!(mpv.client_name().unwrap(), "main");
+    assert_eq!(client.client_name().unwrap(), "second");
+    assert_ne!(mpv.client_id(), client.client_id());
+
+    let mut client_ctx = client.create_event_context();
+    client_ctx.disable_deprecated_events().unwrap();
+    client_ctx
+        .observe_property("volume", Format::Int64, 0)
+        .unwrap();
+    assert_event_occurs!(
+        client_ctx,
+        3.,
+        Ok(Event::PropertyChange {
+            name: "volume",
+            change: PropertyData::Int64(100),
+            reply_userdata: 0,
+        })
+    );
+    mpv.set_property("volume", 0).unwrap();
+    assert_eq!(0i64, client.get_property("volume").unwrap());
+
+    // The core stays alive as long as a strong client exists
+    drop(mpv);
+    assert_eq!(0i64, client.get_property("volume").unwrap());
+
+    let mut weak_ctx = weak.create_event_context();
+    drop(client_ctx);
+    let terminated = thread::spawn(move || drop(client));
+    assert_event_occurs!(weak_ctx, 3., Ok(Event::Shutdown));
+    // A weak client can't revive the core
+    assert_eq!(
+        weak.create_client("revived").err(),
+        Some(Error::Raw(mpv_error::Uninitialized))
+    );
+    drop(weak_ctx);
+    drop(weak);
+    terminated.join().unwrap();
+}
+
+#[test]
+fn shared_controller() {
+    let mpv = Mpv::new().unwrap();
+    let mut ev_ctx = mpv.create_event_context();
+    ev_ctx.disable_deprecated_events().unwrap();
+    ev_ctx.observe_property("volume", Format::Int64, 0).unwrap();
+
+    let controller = mpv.clone();
+    thread::spawn(move || controller.set_property("volume", 0).unwrap())
+        .join()
+        .unwrap();
+
+    // The event context keeps the client alive
+    drop(mpv);
+    assert_event_occurs!(
+        ev_ctx,
+        3.,
+        Ok(Event::PropertyChange {
+            name: "volume",
+            change: PropertyData::Int64(0),
+            reply_userdata: 0,
+        })
+    );
+}
+
+#[test]
+fn shutdown() {
+    let mpv = Mpv::new().unwrap();
+    let client = mpv.create_client("client").unwrap();
+    let mut ev_ctx = client.create_event_context();
+    assert!(!ev_ctx.wait_for_shutdown(0.5));
+
+    mpv.quit(3).unwrap();
+    assert!(ev_ctx.wait_for_shutdown(3.));
+    assert_eq!(client.exit_code(), Some(3));
+
+    // Terminating fails while another handle of the client is alive
+    let clone = mpv.clone();
+    assert_eq!(
+        clone.terminate(),
+        Err(Error::Raw(mpv_error::InvalidParameter))
+    );
+
+    // Terminating blocks until the other strong client is destroyed
+    let (tx, rx) = std::sync::mpsc::channel();
+    thread::spawn(move || {
+        mpv.terminate().unwrap();
+        tx.send(()).unwrap();
+    });
+    assert!(rx.recv_timeout(Duration::from_millis(500)).is_err());
+    drop(ev_ctx);
+    client.detach();
+    rx.recv_timeout(Duration::from_secs(3)).unwrap();
+}
+
+#[test]
+fn owned_events() {
+    let mpv = Mpv::new().unwrap();
+    let mut ev_ctx = mpv.create_event_context();
+    ev_ctx.disable_deprecated_events().unwrap();
+    ev_ctx.observe_property("volume", Format::Int64, 0).unwrap();
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    loop {
+        if let Some(Ok(ev)) = ev_ctx.wait_event(3.) {
+            tx.send(ev.to_owned().unwrap()).unwrap();
+            break;
+        }
+    }
+    let owned = thread::spawn(move || rx.recv().unwrap()).join().unwrap();
+    assert_eq!(
+        owned,
+        OwnedEvent::PropertyChange {
+            name: "volume".to_owned(),
+            change: OwnedPropertyData::Int64(100),
+            reply_userdata: 0,
+        }
+    );
+
+    #[cfg(feature = "serde")]
+    {
+        use serde_test::Token;
+        serde_test::assert_tokens(
+            &owned,
+            &[
+                Token::StructVariant {
+                    name: "OwnedEvent",
+                    variant: "PropertyChange",
+                    len: 3,
+                },
+                Token::Str("name"),
+                Token::Str("volume"),
+                Token::Str("change"),
+                Token::NewtypeVariant {
+                    name: "OwnedPropertyData",
+                    variant: "Int64",
+                },
+                Token::I64(100),
+                Token::Str("reply_userdata"),
+                Token::U64(0),
+                Token::StructVariantEnd,
+            ],
+        );
+    }
+
+    mpv.set_property("volume", 50).unwrap();
+    let node = loop {
+        match ev_ctx.wait_event_node(3.) {
+            Some(Ok(node)) => {
+                if node.get("event").and_then(OwnedNode::to_str) == Some("property-change") {
+                    break node;
+                }
+            }
+            other => panic!("Event did not occur, got: {:?}", other),
+        }
+    };
+    assert_eq!(node.get("name").and_then(OwnedNode::to_str), Some("volume"));
+    assert_eq!(node.get("data").and_then(OwnedNode::to_i64), Some(50));
+
+    let node = ev_ctx.wait_event_node(0.);
+    assert!(node.is_none());
+}
+
+#[test]
+fn tracks() {
+    let (mpv, mut ev_ctx, ()) = play_speech(&[], |_, _| ());
+    assert_event_occurs!(ev_ctx, 3., Ok(Event::FileLoaded));
+
+    let tracks = mpv.tracks().unwrap();
+    assert_eq!(tracks.len(), 1);
+    let audio = &tracks[0];
+    assert_eq!(audio.kind, TrackKind::Audio);
+    assert!(audio.selected);
+    assert!(!audio.external);
+    assert_eq!(audio.demux.channel_count, Some(1));
+    assert_eq!(audio.demux.samplerate, Some(48_000));
+
+    mpv.deselect_track(TrackKind::Audio).unwrap();
+    assert!(!mpv.tracks().unwrap()[0].selected);
+    mpv.select_track(TrackKind::Audio, audio.id).unwrap();
+    assert!(mpv.tracks().unwrap()[0].selected);
+
+    mpv.audio_add_auto("test-data/speech_12kbps_mb.wav", Some("Second track"), None)
+        .unwrap();
+    let tracks = mpv.tracks().unwrap();
+    assert_eq!(tracks.len(), 2);
+    assert!(tracks[1].external);
+    assert_eq!(tracks[1].title.as_deref(), Some("Second track"));
+
+    let err = mpv
+        .audio_add_auto("test-data/speech_12kbps_mb.wav", None, Some("en"))
+        .unwrap_err();
+    assert_eq!(err, Error::Raw(mpv_error::InvalidParameter));
+    let err = mpv
+        .subtitle_add_select("test-data/speech_12kbps_mb.wav", None, Some("en"))
+        .unwrap_err();
+    assert_eq!(err, Error::Raw(mpv_error::InvalidParameter));
+}
+
+#[test]
+fn chapters() {
+    let (mpv, mut ev_ctx, chapter) =
+        play_speech(&[], |_, ev_ctx| ev_ctx.observe_chapter().unwrap());
+    assert_event_occurs!(ev_ctx, 3., Ok(Event::FileLoaded));
+
+    assert_eq!(mpv.chapters().unwrap(), vec![]);
+    assert_eq!(mpv.editions().unwrap(), vec![]);
+    assert_eq!(mpv.current_chapter().unwrap(), None);
+    assert!(mpv.goto_chapter(1).is_err());
+
+    let mut changed = false;
+    while let Some(ev) = ev_ctx.wait_event(1.) {
+        if let Some(value) = chapter.change(&ev.unwrap()) {
+            assert_eq!(value.unwrap(), ObservedValue::Unavailable);
+            changed = true;
+        }
+    }
+    assert!(changed);
+}
+
+#[test]
+fn custom_chapters() {
+    let chapters = vec![
+        Chapter {
+            title: Some("Intro".to_owned()),
+            time: 0.,
+        },
+        Chapter {
+            title: Some("a=b; #c".to_owned()),
+            time: 1.5,
+        },
+        Chapter {
+            title: None,
+            time: 3.,
+        },
+    ];
+    assert_eq!(
+        Chapter::to_ffmetadata(&[chapters[1].clone(), chapters[0].clone()], Some(2.)),
+        ";FFMETADATA1\n\
+         [CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=1500\ntitle=Intro\n\
+         [CHAPTER]\nTIMEBASE=1/1000\nSTART=1500\nEND=2000\ntitle=a\\=b\\; \\#c\n"
+    );
+    assert!(Chapter::to_ffmetadata(&chapters[1..2], None)
+        .ends_with("START=1500\nEND=1500\ntitle=a\\=b\\; \\#c\n"));
+
+    let mpv = Mpv::builder().vo("null").ao("null").build().unwrap();
+    let mut ev_ctx = mpv.create_event_context();
+    ev_ctx.disable_deprecated_events().unwrap();
+    mpv.playlist_load_file_with_chapters(
+        "test-data/speech_12kbps_mb.wav",
+        FileState::AppendPlay,
+        &chapters,
+    )
+    .unwrap();
+    assert_event_occurs!(ev_ctx, 3., Ok(Event::FileLoaded));
+
+    assert_eq!(mpv.chapters().unwrap(), chapters);
+    mpv.goto_chapter(1).unwrap();
+    assert_eq!(mpv.current_chapter().unwrap(), Some(1));
+}
+
+#[test]
+fn playlist() {
+    let mpv = Mpv::new().unwrap();
+    let file = "test-data/speech_12kbps_mb.wav";
+    mpv.playlist_load_files(&[
+        (file, FileState::Append, None),
+        (file, FileState::Append, Some("start=1,end=2")),
+    ])
+    .unwrap();
+
+    let playlist = mpv.playlist().unwrap();
+    assert_eq!(playlist.len(), 2);
+    assert_eq!(playlist[0].filename, file);
+    assert_ne!(playlist[0].id, playlist[1].id);
+    let (first, second) = (playlist[0].id, playlist[1].id);
+
+    let inserted = mpv.playlist_insert_at(file, 1, None).unwrap();
+    let ids = |mpv: &Mpv| {
+        mpv.playlist()
+            .unwrap()
+            .iter()
+            .map(|e| e.id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&mpv), vec![first, inserted, second]);
+    assert_eq!(mpv.playlist_position(second).unwrap(), Some(2));
+
+    mpv.playlist_move_id(first, None).unwrap();
+    assert_eq!(ids(&mpv), vec![inserted, second, first]);
+    mpv.playlist_move_id(first, Some(inserted)).unwrap();
+    assert_eq!(ids(&mpv), vec![first, inserted, second]);
+
+    mpv.playlist_remove_id(inserted).unwrap();
+    assert_eq!(ids(&mpv), vec![first, second]);
+    assert!(mpv.playlist_remove_id(inserted).is_err());
+
+    mpv.playlist_play_id(second).unwrap();
+    assert!(mpv.playlist().unwrap()[1].current);
+}
+
+#[test]
+fn playlist_tracker() {
+    let mpv = Mpv::builder()
+        .vo("null")
+        .ao("null")
+        .option("end", "0.1")
+        .build()
+        .unwrap();
+    let mut ev_ctx = mpv.create_event_context();
+    ev_ctx.disable_deprecated_events().unwrap();
+
+    let file = std::fs::canonicalize("test-data/speech_12kbps_mb.wav").unwrap();
+    let list = std::env::temp_dir().join(format!("libmpv-rs-test-{}.m3u", std::process::id()));
+    std::fs::write(&list, format!("{}\n", file.display())).unwrap();
+
+    let ids = mpv
+        .playlist_load_files(&[
+            (file.to_str().unwrap(), FileState::AppendPlay, None),
+            (list.to_str().unwrap(), FileState::Append, None),
+        ])
+        .unwrap();
+    assert_eq!(ids.len(), 2);
+    let mut tracker = PlaylistTracker::new();
+    tracker.track(ids[0], "file");
+    tracker.track(ids[1], "list");
+
+    let mut tracked = Vec::new();
+    while let Some(ev) = ev_ctx.wait_event(3.) {
+        tracked.extend(tracker.handle_event(&ev.unwrap()));
+        // The entry inserted by the list is the last one to end
+        if let Some(TrackedEvent::Ended { key: "list", .. }) = tracked.last() {
+            break;
+        }
+    }
+    let _ = std::fs::remove_file(&list);
+
+    let inserted = match tracked[3] {
+        TrackedEvent::Redirected {
+            key: "list",
+            ref inserted,
+            ..
+        } => inserted[0],
+        ref ev => panic!("unexpected {:?}", ev),
+    };
+    assert_eq!(inserted, ids[1] + 1);
+    assert_eq!(tracker.key(ids[1]), None);
+    assert_eq!(tracker.key(inserted), Some(&"list"));
+    assert_eq!(
+        tracked[4],
+        TrackedEvent::Started {
+            key: "list",
+            playlist_entry_id: inserted
+        }
+    );
+    assert!(matches!(
+        tracked[5],
+        TrackedEvent::Ended {
+            key: "list",
+            reason: mpv_end_file_reason::Eof,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn playlist_formats() {
+    let entries = |list: &[(&str, Option<&str>)]| {
+        list.iter()
+            .map(|&(filename, title)| PlaylistEntry {
+                id: 0,
+                filename: filename.to_owned(),
+                title: title.map(str::to_owned),
+                current: false,
+                playing: false,
+            })
+            .collect::<Vec<_>>()
+    };
+    let expected = entries(&[
+        ("/music/a b.flac", Some("Artist - A & B")),
+        ("https://example.org/stream", None),
+    ]);
+
+    let m3u = "\u{feff}#EXTM3U\n#EXTINF:123 tvg-id=\"x\",Artist - A & B\n/music/a b.flac\n\n\
+               # comment\nhttps://example.org/stream\n";
+    assert_eq!(PlaylistFormat::M3u.parse(m3u).unwrap(), expected);
+
+    let pls = "[playlist]\nFile2=https://example.org/stream\nfile1=/music/a b.flac\n\
+               Title1=Artist - A & B\nLength1=123\nNumberOfEntries=2\n";
+    assert_eq!(PlaylistFormat::Pls.parse(pls).unwrap(), expected);
+    assert!(PlaylistFormat::Pls.parse("File1=a").is_err());
+
+    let xspf = r#"<?xml version="1.0"?>
+        <playlist version="1" xmlns="http://xspf.org/ns/0/"><trackList>
+          <track><title>Artist - A &amp; B</title><location>file:///music/a%20b.flac</location></track>
+          <track><location>https://example.org/stream</location><title/></track>
+        </trackList></playlist>"#;
+    assert_eq!(PlaylistFormat::Xspf.parse(xspf).unwrap(), expected);
+
+    for &format in &[
+        PlaylistFormat::M3u,
+        PlaylistFormat::Pls,
+        PlaylistFormat::Xspf,
+    ] {
+        assert_eq!(
+            format.parse(&format.export(&expected).unwrap()).unwrap(),
+            expected
+        );
+    }
+    let relative = entries(&[("music/c d%.mp3", None)]);
+    let xspf = PlaylistFormat::Xspf.export(&relative).unwrap();
+    assert_eq!(PlaylistFormat::Xspf.parse(&xspf).unwrap(), relative);
+    let xspf = "<playlist><trackList><track><location>file:///C:/c%20d.mp3</location></track>\
+                </trackList></playlist>";
+    assert_eq!(
+        PlaylistFormat::Xspf.parse(xspf).unwrap(),
+        entries(&[("C:/c d.mp3", None)])
+    );
+    let injected = entries(&[("a.mp3\nb.mp3", None)]);
+    assert!(PlaylistFormat::M3u.export(&injected).is_err());
+    assert!(PlaylistFormat::Pls.export(&injected).is_err());
+    let titled = entries(&[("a.mp3", Some("A\nB"))]);
+    assert_eq!(
+        PlaylistFormat::M3u
+            .parse(&PlaylistFormat::M3u.export(&titled).unwrap())
+            .unwrap()[0]
+            .title
+            .as_deref(),
+        Some("A B")
+    );
+    assert_eq!(
+        PlaylistFormat::from_path("list.M3U8"),
+        Some(PlaylistFormat::M3u)
+    );
+    assert_eq!(PlaylistFormat::from_path("list"), None);
+
+    let mpv = Mpv::new().unwrap();
+    let ids = mpv
+        .playlist_load_entries(&expected, FileState::Append)
+        .unwrap();
+    assert_eq!(ids.len(), 2);
+    let playlist = mpv.playlist().unwrap();
+    assert_eq!(
+        playlist.iter().map(|entry| entry.id).collect::<Vec<_>>(),
+        ids
+    );
+    let exported = PlaylistFormat::M3u
+        .parse(&mpv.playlist_export(PlaylistFormat::M3u).unwrap())
+        .unwrap();
+    assert_eq!(exported, expected);
+}
+
+#[test]
+fn metadata() {
+    let node = OwnedNode::Map(
+        [
+            ("TITLE", "Song"),
+            ("Artist", "Band"),
+            ("album", "Record"),
+            ("year", "1999"),
+            ("track", "3/12"),
+            ("icy-title", "Band - Song"),
+        ]
+        .iter()
+        .map(|&(k, v)| (k.to_owned(), OwnedNode::String(v.to_owned())))
+        .collect(),
+    );
+    let metadata = Metadata::from_node(&node).unwrap();
+    assert_eq!(metadata.title(), Some("Song"));
+    assert_eq!(metadata.artist(), Some("Band"));
+    assert_eq!(metadata.album(), Some("Record"));
+    assert_eq!(metadata.date(), Some("1999"));
+    assert_eq!(metadata.track_number(), Some(3));
+    assert_eq!(metadata.icy_title(), Some("Band - Song"));
+    assert_eq!(metadata.get("Title"), Some("Song"));
+    assert!(Metadata::from_node(&OwnedNode::Int64(0)).is_err());
+
+    let (mpv, mut ev_ctx, observer) = play_speech(&[], |_, ev_ctx| {
+        ev_ctx.observe_key(&properties::METADATA).unwrap()
+    });
+
+    let mut tags = None;
+    while let Some(ev) = ev_ctx.wait_event(1.) {
+        if let Some(ObservedValue::Value(value)) =
+            observer.change(&ev.unwrap()).transpose().unwrap()
+        {
+            tags = Some(value);
+        }
+    }
+    assert_eq!(tags, Some(mpv.metadata().unwrap()));
+    assert_eq!(mpv.filtered_metadata().unwrap().title(), None);
+}
+
+#[test]
+fn audio_devices() {
+    let mpv = Mpv::builder().ao("null").build().unwrap();
+    let mut ev_ctx = mpv.create_event_context();
+    ev_ctx.disable_deprecated_events().unwrap();
+    let observer = ev_ctx.observe_key(&properties::AUDIO_DEVICE_LIST).unwrap();
+
+    let devices = mpv.audio_devices().unwrap();
+    assert_eq!(devices[0].name, "auto");
+    assert!(devices.iter().any(|device| device.name == "null"));
+    assert_eq!(mpv.audio_device().unwrap(), "auto");
+
+    mpv.set_audio_device("null").unwrap();
+    assert_eq!(mpv.audio_device().unwrap(), "null");
+
+    let mut observed = None;
+    while let Some(ev) = ev_ctx.wait_event(1.) {
+        if let Some(value) = observer.change(&ev.unwrap()) {
+            observed = Some(value.unwrap());
+        }
+    }
+    assert_eq!(observed, Some(ObservedValue::Value(devices)));
+}
+
+#[test]
+fn media_params() {
+    let node = OwnedNode::Map(vec![
+        (
+            "pixelformat".to_owned(),
+            OwnedNode::String("yuv420p".to_owned()),
+        ),
+        ("w".to_owned(), OwnedNode::Int64(1920)),
+        ("h".to_owned(), OwnedNode::Int64(1080)),
+        ("aspect".to_owned(), OwnedNode::Double(16. / 9.)),
+        ("rotate".to_owned(), OwnedNode::Int64(90)),
+    ]);
+    let video = VideoParams::from_node(&node).unwrap();
+    assert_eq!(video.pixelformat.as_deref(), Some("yuv420p"));
+    assert_eq!(
+        (video.w, video.h, video.rotate),
+        (Some(1920), Some(1080), Some(90))
+    );
+    assert_eq!(video.colormatrix, None);
+
+    let (mpv, mut ev_ctx, ()) = play_speech(&[], |_, ev_ctx| ev_ctx.set_reconfig_params(true));
+
+    let mut reconfigured = None;
+    while let Some(ev) = ev_ctx.wait_event(3.) {
+        match ev.unwrap() {
+            Event::AudioReconfig {
+                out_params: Some(params),
+                ..
+            } => reconfigured = Some(*params),
+            Event::PlaybackRestart => break,
+            _ => {}
+        }
+    }
+    let params = reconfigured.unwrap();
+    assert_eq!(params, mpv.audio_out_params().unwrap());
+    assert_eq!(params.samplerate, Some(48_000));
+    assert_eq!(mpv.audio_params().unwrap().channel_count, Some(1));
+    assert!(mpv.video_params().is_err());
+}
+
+#[test]
+fn seek() {
+    assert_eq!(
+        Seek::absolute(2.).exact(),
+        Seek::new(SeekTarget::Absolute(2.)).precision(SeekPrecision::Exact)
+    );
+
+    let (mpv, mut ev_ctx, ()) = play_speech(&[("pause", "yes")], |_, _| ());
+    assert_event_occurs!(ev_ctx, 3., Ok(Event::PlaybackRestart));
+
+    let _volume = ev_ctx.observe::<i64>("volume").unwrap();
+    let wait = ev_ctx
+        .seek_and_wait(&Seek::absolute(2.).exact(), 3.)
+        .unwrap();
+    assert_eq!(wait.result, Ok(true));
+    assert!((mpv.get(&properties::TIME_POS).unwrap() - 2.).abs() < 0.01);
+    // Other events are returned instead of being discarded
+    assert!(wait.skipped.iter().any(|ev| matches!(
+        ev,
+        Ok(OwnedEvent::PropertyChange { name, .. }) if name == "volume"
+    )));
+
+    // The restart of an earlier seek is not mistaken for the restart of this one
+    mpv.seek(&Seek::absolute(3.).exact()).unwrap();
+    let wait = ev_ctx
+        .seek_and_wait(&Seek::relative(-2.).exact(), 3.)
+        .unwrap();
+    assert_eq!(wait.result, Ok(true));
+    assert!((mpv.get(&properties::TIME_POS).unwrap() - 1.).abs() < 0.01);
+
+    mpv.seek_percent_absolute(50).unwrap();
+    assert_event_occurs!(ev_ctx, 3., Ok(Event::PlaybackRestart));
+    assert!((mpv.get(&properties::PERCENT_POS).unwrap() - 50.).abs() < 1.);
+
+    assert!(mpv.seek(&Seek::chapter(0)).is_err());
+    // The file has no video, so no frame rate
+    assert_eq!(
+        mpv.seek(&Seek::frame(10)),
+        Err(Error::Raw(mpv_error::InvalidParameter))
+    );
+}
+
+#[test]
+fn loops() {
+    let (mpv, mut ev_ctx, mut loops) = play_speech(&[], |mpv, ev_ctx| {
+        assert_eq!(mpv.ab_loop().unwrap(), (None, None));
+        mpv.ab_loop_set(1., 2.5).unwrap();
+        assert_eq!(mpv.ab_loop().unwrap(), (Some(1.), Some(2.5)));
+
+        mpv.set_loop_file(Loop::Times(2)).unwrap();
+        assert_eq!(mpv.loop_file().unwrap(), Loop::Times(2));
+        assert_eq!(
+            mpv.set_loop_file(Loop::Force),
+            Err(Error::Raw(mpv_error::InvalidParameter))
+        );
+        assert_eq!(
+            mpv.set_ab_loop_count(Loop::Force),
+            Err(Error::Raw(mpv_error::InvalidParameter))
+        );
+        mpv.set_loop_playlist(Loop::Force).unwrap();
+        assert_eq!(mpv.loop_playlist().unwrap(), Loop::Force);
+        mpv.set_loop_playlist(Loop::No).unwrap();
+        mpv.set_ab_loop_count(Loop::Inf).unwrap();
+        assert_eq!(mpv.ab_loop_count().unwrap(), Loop::Inf);
+
+        ev_ctx.observe_loops().unwrap()
+    });
+
+    let mut wraps = 0;
+    let mut seeked = None;
+    while let Some(ev) = ev_ctx.wait_event(3.) {
+        if let Some(wrap) = loops.wrapped(&ev.unwrap()) {
+            assert_eq!(wrap, LoopWrap::AbLoop);
+            wraps += 1;
+            if wraps == 2 {
+                break;
+            }
+            // A seek to A from the middle of the loop is not a wrap
+            mpv.seek(&Seek::absolute(1.).exact()).unwrap();
+            seeked = Some(Instant::now());
+        }
+    }
+    assert_eq!(wraps, 2);
+    assert!(seeked.unwrap().elapsed() >= Duration::from_secs(1));
+
+    // With a finite count, a seek by the user from near B to A is not a wrap
+    mpv.set_property("pause", true).unwrap();
+    mpv.set_ab_loop_count(Loop::Times(2)).unwrap();
+    mpv.set_loop_file(Loop::No).unwrap();
+    for &pos in &[2.3, 1.] {
+        mpv.seek(&Seek::absolute(pos).exact()).unwrap();
+        let mut restarted = false;
+        while let Some(ev) = ev_ctx.wait_event(3.) {
+            let ev = ev.unwrap();
+            assert_eq!(loops.wrapped(&ev), None);
+            match ev {
+                Event::PlaybackRestart => restarted = true,
+                Event::PropertyChange {
+                    name: "time-pos", ..
+                } if restarted => break,
+                _ => {}
+            }
+        }
+        assert!(restarted);
+    }
+
+    // The loop is repeated as often as its count, then playback continues to the end
+    mpv.set_property("pause", false).unwrap();
+    let mut wraps = 0;
+    loop {
+        match ev_ctx.wait_event(5.) {
+            Some(Ok(Event::EndFile { .. })) => break,
+            Some(Ok(ev)) => {
+                if let Some(wrap) = loops.wrapped(&ev) {
+                    assert_eq!(wrap, LoopWrap::AbLoop);
+                    wraps += 1;
+                }
+            }
+            ev => panic!("unexpected event {:?}", ev),
+        }
+    }
+    assert_eq!(wraps, 2);
+
+    mpv.ab_loop_clear().unwrap();
+    assert_eq!(mpv.ab_loop().unwrap(), (None, None));
+}
+
 #[test]
 fn node_map() -> Result<()> {
     let mpv = Mpv::new()?;
@@ -196,6 +1315,18 @@ fn node_map() -> Result<()> {
     let channel_count = params.get("channel-count").unwrap().value()?;
     assert!(matches!(channel_count, MpvNodeValue::Int64(1)));
 
+    let expanded = mpv.command_node(&OwnedNode::Map(vec![
+        (
+            "name".to_owned(),
+            OwnedNode::String("expand-text".to_owned()),
+        ),
+        (
+            "text".to_owned(),
+            OwnedNode::String("${samplerate}".to_owned()),
+        ),
+    ]))?;
+    assert_eq!(expanded, OwnedNode::String("48000".to_owned()));
+
     Ok(())
 }
 